@@ -0,0 +1,306 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use std::fmt::{self, Display};
+
+/// Brightness, contrast and saturation added or removed by the keys.
+pub const STEP: f32 = 0.1;
+
+/// Factor the gamma is multiplied or divided by with the keys.
+pub const GAMMA_STEP: f32 = 1.25;
+
+/// Share of the darkest and brightest pixels ignored by auto levels.
+const LEVELS_CLIP: f32 = 0.005;
+
+/// Color adjustments applied to the decoded image before it is rendered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Adjustments {
+    /// Added to every channel, `-1.0..=1.0`.
+    pub brightness: f32,
+    /// `-1.0..=1.0`, `-1.0` turns everything to mid gray.
+    pub contrast: f32,
+    pub gamma: f32,
+    /// `0.0` is gray and `1.0` keeps the colors.
+    pub saturation: f32,
+    pub invert: bool,
+    pub sepia: bool,
+    pub levels: Levels,
+}
+
+/// How the tonal range is stretched before the other adjustments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Levels {
+    #[default]
+    Unchanged,
+    /// Stretches the range of the pixels to black and white.
+    Auto,
+    /// Histogram equalization.
+    Equalize,
+}
+
+impl Default for Adjustments {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 0.0,
+            gamma: 1.0,
+            saturation: 1.0,
+            invert: false,
+            sepia: false,
+            levels: Levels::Unchanged,
+        }
+    }
+}
+
+impl Levels {
+    pub fn next(self) -> Self {
+        match self {
+            Levels::Unchanged => Levels::Auto,
+            Levels::Auto => Levels::Equalize,
+            Levels::Equalize => Levels::Unchanged,
+        }
+    }
+
+    /// Maps the values of the channels following the luma histogram of `img`.
+    fn table(self, img: &RgbaImage) -> [u8; 256] {
+        let mut table = std::array::from_fn(|i| i as u8);
+        if self == Levels::Unchanged {
+            return table;
+        }
+
+        let mut histogram = [0u32; 256];
+        for Rgba([r, g, b, a]) in img.pixels() {
+            if *a != 0 {
+                histogram[luma(*r, *g, *b) as usize] += 1;
+            }
+        }
+        let total: u32 = histogram.iter().sum();
+        if total == 0 {
+            return table;
+        }
+
+        let mut cdf = [0u32; 256];
+        let mut sum = 0;
+        for (i, count) in histogram.iter().enumerate() {
+            sum += count;
+            cdf[i] = sum;
+        }
+
+        match self {
+            Levels::Unchanged => {}
+            Levels::Auto => {
+                let clip = (total as f32 * LEVELS_CLIP) as u32;
+                let low = cdf.iter().position(|&c| c > clip).unwrap_or(0);
+                let high = cdf.iter().position(|&c| c >= total - clip).unwrap_or(255);
+                if high > low {
+                    for (i, v) in table.iter_mut().enumerate() {
+                        let stretched = (i as f32 - low as f32) / (high - low) as f32;
+                        *v = (stretched.clamp(0.0, 1.0) * 255.0).round() as u8;
+                    }
+                }
+            }
+            Levels::Equalize => {
+                let first = cdf.iter().copied().find(|&c| c > 0).unwrap_or(0);
+                if total > first {
+                    for (i, v) in table.iter_mut().enumerate() {
+                        let equalized =
+                            cdf[i].saturating_sub(first) as f32 / (total - first) as f32;
+                        *v = (equalized * 255.0).round() as u8;
+                    }
+                }
+            }
+        }
+
+        table
+    }
+}
+
+impl Adjustments {
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns `value + delta` rounded to the keys step, within `min..=max`.
+    pub fn step(value: f32, delta: f32, min: f32, max: f32) -> f32 {
+        // Adding zero turns -0.0 into 0.0.
+        (((value + delta) / STEP).round() * STEP).clamp(min, max) + 0.0
+    }
+
+    pub fn apply(&self, img: DynamicImage) -> DynamicImage {
+        if self.is_identity() {
+            return img;
+        }
+
+        let mut img = img.to_rgba8();
+        let levels = self.levels.table(&img);
+        let mut table = [0u8; 256];
+        for (i, v) in table.iter_mut().enumerate() {
+            let x = levels[i] as f32 / 255.0;
+            let x = ((x - 0.5) * (1.0 + self.contrast) + 0.5 + self.brightness).clamp(0.0, 1.0);
+            *v = (x.powf(1.0 / self.gamma) * 255.0).round() as u8;
+        }
+
+        for Rgba([r, g, b, _]) in img.pixels_mut() {
+            let (mut red, mut green, mut blue) = (
+                table[*r as usize] as f32,
+                table[*g as usize] as f32,
+                table[*b as usize] as f32,
+            );
+
+            if self.saturation != 1.0 {
+                let gray = 0.299 * red + 0.587 * green + 0.114 * blue;
+                red = gray + (red - gray) * self.saturation;
+                green = gray + (green - gray) * self.saturation;
+                blue = gray + (blue - gray) * self.saturation;
+            }
+            if self.sepia {
+                (red, green, blue) = (
+                    0.393 * red + 0.769 * green + 0.189 * blue,
+                    0.349 * red + 0.686 * green + 0.168 * blue,
+                    0.272 * red + 0.534 * green + 0.131 * blue,
+                );
+            }
+
+            let channel = |v: f32| {
+                let v = v.round().clamp(0.0, 255.0) as u8;
+                if self.invert {
+                    255 - v
+                } else {
+                    v
+                }
+            };
+            (*r, *g, *b) = (channel(red), channel(green), channel(blue));
+        }
+
+        DynamicImage::ImageRgba8(img)
+    }
+}
+
+impl Display for Levels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            Levels::Unchanged => "unchanged",
+            Levels::Auto => "auto levels",
+            Levels::Equalize => "equalized",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl Display for Adjustments {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let default = Self::default();
+        let mut parts = vec![];
+        if self.levels != default.levels {
+            parts.push(self.levels.to_string());
+        }
+        if self.brightness != default.brightness {
+            parts.push(format!("brightness {:+.1}", self.brightness));
+        }
+        if self.contrast != default.contrast {
+            parts.push(format!("contrast {:+.1}", self.contrast));
+        }
+        if self.gamma != default.gamma {
+            parts.push(format!("gamma {:.2}", self.gamma));
+        }
+        if self.saturation != default.saturation {
+            parts.push(format!("saturation {:.1}", self.saturation));
+        }
+        if self.sepia {
+            parts.push("sepia".to_string());
+        }
+        if self.invert {
+            parts.push("inverted".to_string());
+        }
+
+        if parts.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+/// Luma of a color, as in ITU-R BT.601.
+fn luma(r: u8, g: u8, b: u8) -> u8 {
+    ((299 * r as u32 + 587 * g as u32 + 114 * b as u32 + 500) / 1000) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(adjustments: Adjustments, pixels: &[[u8; 4]]) -> Vec<[u8; 4]> {
+        let img = RgbaImage::from_fn(pixels.len() as u32, 1, |x, _| Rgba(pixels[x as usize]));
+        adjustments
+            .apply(DynamicImage::ImageRgba8(img))
+            .to_rgba8()
+            .pixels()
+            .map(|pixel| pixel.0)
+            .collect()
+    }
+
+    fn grays(values: &[u8]) -> RgbaImage {
+        RgbaImage::from_fn(values.len() as u32, 1, |x, _| {
+            let v = values[x as usize];
+            Rgba([v, v, v, 255])
+        })
+    }
+
+    #[test]
+    fn leaves_pixels_unchanged_by_default() {
+        let pixels = [[0, 0, 0, 0], [10, 20, 30, 40], [255, 128, 1, 255]];
+        assert_eq!(apply(Adjustments::default(), &pixels), pixels);
+
+        let table = Levels::Unchanged.table(&grays(&[100, 150]));
+        assert!(table.iter().enumerate().all(|(i, v)| *v as usize == i));
+    }
+
+    #[test]
+    fn inverts_colors_but_not_alpha() {
+        let inverted = Adjustments {
+            invert: true,
+            ..Adjustments::default()
+        };
+        assert_eq!(apply(inverted, &[[10, 20, 30, 40]]), [[245, 235, 225, 40]]);
+    }
+
+    #[test]
+    fn tints_white_with_sepia() {
+        let sepia = Adjustments {
+            sepia: true,
+            ..Adjustments::default()
+        };
+        assert_eq!(
+            apply(sepia, &[[255, 255, 255, 255]]),
+            [[255, 255, 239, 255]]
+        );
+        assert_eq!(apply(sepia, &[[0, 0, 0, 255]]), [[0, 0, 0, 255]]);
+    }
+
+    #[test]
+    fn stretches_a_narrow_histogram() {
+        let table = Levels::Auto.table(&grays(&[100, 150, 100, 150]));
+        assert_eq!((table[0], table[100], table[125]), (0, 0, 128));
+        assert_eq!((table[150], table[255]), (255, 255));
+
+        let auto = Adjustments {
+            levels: Levels::Auto,
+            ..Adjustments::default()
+        };
+        let pixels = [[100, 100, 100, 255], [150, 150, 150, 255], [255, 0, 0, 0]];
+        assert_eq!(
+            apply(auto, &pixels),
+            [[0, 0, 0, 255], [255, 255, 255, 255], [255, 0, 0, 0]]
+        );
+    }
+
+    #[test]
+    fn equalizes_a_two_value_image() {
+        let table = Levels::Equalize.table(&grays(&[50, 200, 50, 200]));
+        assert_eq!((table[0], table[50], table[100]), (0, 0, 0));
+        assert_eq!((table[200], table[255]), (255, 255));
+
+        // A single value has nothing to spread.
+        let table = Levels::Equalize.table(&grays(&[50, 50]));
+        assert_eq!(table[50], 50);
+    }
+}
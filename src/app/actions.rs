@@ -8,20 +8,104 @@ use std::{
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum Action {
     Quit,
+    Help,
     Increment,
     Decrement,
     Show,
     Search,
+    SwitchRenderer,
+    PauseAnimation,
+    NextFrame,
+    PrevFrame,
+    ToneMap,
+    IncreaseExposure,
+    DecreaseExposure,
+    View,
+    ZoomIn,
+    ZoomOut,
+    ActualSize,
+    FitWidth,
+    FitHeight,
+    Fit,
+    PanLeft,
+    PanDown,
+    PanUp,
+    PanRight,
+    LeaveView,
+    RotateLeft,
+    RotateRight,
+    FlipHorizontal,
+    FlipVertical,
+    FitMode,
+    Filter,
+    Backdrop,
+    ColorMode,
+    Adjust,
+    IncreaseBrightness,
+    DecreaseBrightness,
+    IncreaseContrast,
+    DecreaseContrast,
+    IncreaseGamma,
+    DecreaseGamma,
+    IncreaseSaturation,
+    DecreaseSaturation,
+    Invert,
+    Sepia,
+    Levels,
+    ResetAdjustments,
+    LeaveAdjust,
 }
 
 impl Action {
     pub fn iterator() -> Iter<'static, Action> {
-        static ACTIONS: [Action; 5] = [
+        static ACTIONS: [Action; 47] = [
             Action::Quit,
+            Action::Help,
             Action::Increment,
             Action::Decrement,
             Action::Show,
             Action::Search,
+            Action::SwitchRenderer,
+            Action::PauseAnimation,
+            Action::NextFrame,
+            Action::PrevFrame,
+            Action::ToneMap,
+            Action::IncreaseExposure,
+            Action::DecreaseExposure,
+            Action::View,
+            Action::ZoomIn,
+            Action::ZoomOut,
+            Action::ActualSize,
+            Action::FitWidth,
+            Action::FitHeight,
+            Action::Fit,
+            Action::PanLeft,
+            Action::PanDown,
+            Action::PanUp,
+            Action::PanRight,
+            Action::LeaveView,
+            Action::RotateLeft,
+            Action::RotateRight,
+            Action::FlipHorizontal,
+            Action::FlipVertical,
+            Action::FitMode,
+            Action::Filter,
+            Action::Backdrop,
+            Action::ColorMode,
+            Action::Adjust,
+            Action::IncreaseBrightness,
+            Action::DecreaseBrightness,
+            Action::IncreaseContrast,
+            Action::DecreaseContrast,
+            Action::IncreaseGamma,
+            Action::DecreaseGamma,
+            Action::IncreaseSaturation,
+            Action::DecreaseSaturation,
+            Action::Invert,
+            Action::Sepia,
+            Action::Levels,
+            Action::ResetAdjustments,
+            Action::LeaveAdjust,
         ];
         ACTIONS.iter()
     }
@@ -29,10 +113,52 @@ impl Action {
     pub fn keys(&self) -> &[Key] {
         match self {
             Action::Quit => &[Key::Char('q'), Key::Ctrl('c')],
+            Action::Help => &[Key::Char('?')],
             Action::Increment => &[Key::Char('j'), Key::Ctrl('n'), Key::Down],
             Action::Decrement => &[Key::Char('k'), Key::Ctrl('p'), Key::Up],
             Action::Show => &[Key::Enter, Key::Ctrl('m')],
             Action::Search => &[Key::Char('/'), Key::Ctrl('f')],
+            Action::SwitchRenderer => &[Key::Char('r')],
+            Action::PauseAnimation => &[Key::Char(' ')],
+            Action::NextFrame => &[Key::Char('.')],
+            Action::PrevFrame => &[Key::Char(',')],
+            Action::ToneMap => &[Key::Char('t')],
+            Action::IncreaseExposure => &[Key::Char('e')],
+            Action::DecreaseExposure => &[Key::Char('E')],
+            Action::View => &[Key::Char('z')],
+            Action::ZoomIn => &[Key::Char('+'), Key::Char('=')],
+            Action::ZoomOut => &[Key::Char('-')],
+            Action::ActualSize => &[Key::Char('1')],
+            Action::FitWidth => &[Key::Char('W')],
+            Action::FitHeight => &[Key::Char('H')],
+            Action::Fit => &[Key::Char('f'), Key::Char('0')],
+            Action::PanLeft => &[Key::Char('h'), Key::Left],
+            Action::PanDown => &[Key::Char('j'), Key::Down],
+            Action::PanUp => &[Key::Char('k'), Key::Up],
+            Action::PanRight => &[Key::Char('l'), Key::Right],
+            Action::LeaveView => &[Key::Esc, Key::Char('z')],
+            Action::RotateLeft => &[Key::Char('<')],
+            Action::RotateRight => &[Key::Char('>')],
+            Action::FlipHorizontal => &[Key::Char('|')],
+            Action::FlipVertical => &[Key::Char('_')],
+            Action::FitMode => &[Key::Char('f')],
+            Action::Filter => &[Key::Char('F')],
+            Action::Backdrop => &[Key::Char('b')],
+            Action::ColorMode => &[Key::Char('m')],
+            Action::Adjust => &[Key::Char('a')],
+            Action::IncreaseBrightness => &[Key::Char('b')],
+            Action::DecreaseBrightness => &[Key::Char('B')],
+            Action::IncreaseContrast => &[Key::Char('c')],
+            Action::DecreaseContrast => &[Key::Char('C')],
+            Action::IncreaseGamma => &[Key::Char('g')],
+            Action::DecreaseGamma => &[Key::Char('G')],
+            Action::IncreaseSaturation => &[Key::Char('s')],
+            Action::DecreaseSaturation => &[Key::Char('S')],
+            Action::Invert => &[Key::Char('i')],
+            Action::Sepia => &[Key::Char('p')],
+            Action::Levels => &[Key::Char('l')],
+            Action::ResetAdjustments => &[Key::Char('0')],
+            Action::LeaveAdjust => &[Key::Esc, Key::Char('a')],
         }
     }
 }
@@ -41,10 +167,52 @@ impl Display for Action {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let str = match self {
             Action::Quit => "Quit",
+            Action::Help => "Help",
             Action::Increment => "Next",
             Action::Decrement => "Prev",
             Action::Show => "Show",
             Action::Search => "Search",
+            Action::SwitchRenderer => "Renderer",
+            Action::PauseAnimation => "Pause",
+            Action::NextFrame => "Next frame",
+            Action::PrevFrame => "Prev frame",
+            Action::ToneMap => "Tone map",
+            Action::IncreaseExposure => "Exposure +",
+            Action::DecreaseExposure => "Exposure -",
+            Action::View => "Zoom and pan",
+            Action::ZoomIn => "Zoom in",
+            Action::ZoomOut => "Zoom out",
+            Action::ActualSize => "Actual size",
+            Action::FitWidth => "Fit width",
+            Action::FitHeight => "Fit height",
+            Action::Fit => "Fit",
+            Action::PanLeft => "Left",
+            Action::PanDown => "Down",
+            Action::PanUp => "Up",
+            Action::PanRight => "Right",
+            Action::LeaveView => "Back",
+            Action::RotateLeft => "Rotate left",
+            Action::RotateRight => "Rotate right",
+            Action::FlipHorizontal => "Flip horizontally",
+            Action::FlipVertical => "Flip vertically",
+            Action::FitMode => "Fit mode",
+            Action::Filter => "Filter",
+            Action::Backdrop => "Backdrop",
+            Action::ColorMode => "Color mode",
+            Action::Adjust => "Adjust",
+            Action::IncreaseBrightness => "Brightness +",
+            Action::DecreaseBrightness => "Brightness -",
+            Action::IncreaseContrast => "Contrast +",
+            Action::DecreaseContrast => "Contrast -",
+            Action::IncreaseGamma => "Gamma +",
+            Action::DecreaseGamma => "Gamma -",
+            Action::IncreaseSaturation => "Saturation +",
+            Action::DecreaseSaturation => "Saturation -",
+            Action::Invert => "Invert",
+            Action::Sepia => "Sepia",
+            Action::Levels => "Levels",
+            Action::ResetAdjustments => "Reset",
+            Action::LeaveAdjust => "Back",
         };
         write!(f, "{}", str)
     }
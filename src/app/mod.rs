@@ -3,13 +3,19 @@ pub mod state;
 pub mod ui;
 
 use crate::{
+    adjust::{self, Adjustments},
     app::{
         actions::{Action, Actions},
         state::AppState,
     },
+    graphics::Protocol,
     inputs::key::Key,
     io::IoEvent,
-    utils::ImageMode,
+    renderer::{RenderOptions, RendererKind},
+    tone_map::{self, ToneMap},
+    transform::{Flip, Transform},
+    utils::{FileFilter, ImageMode},
+    viewport::{Viewport, Zoom},
 };
 
 use self::state::AppMode;
@@ -23,39 +29,72 @@ pub enum AppReturn {
 #[derive(Debug, Clone)]
 pub struct AppConfig {
     pub image_mode: ImageMode,
+    pub renderer: RendererKind,
+    pub render_options: RenderOptions,
+    pub protocol: Option<Protocol>,
+    pub file_filter: FileFilter,
+    pub tone_map: ToneMap,
+    /// Rotation and flip of every image, after its orientation.
+    pub transform: Transform,
+    pub adjustments: Adjustments,
 }
 
 #[derive(Clone)]
 pub struct App<'a> {
     io_tx: tokio::sync::mpsc::Sender<IoEvent>,
     actions: Actions,
+    /// Actions of the view mode.
+    view_actions: Actions,
+    /// Actions of the adjust mode.
+    adjust_actions: Actions,
     is_loading: bool,
+    /// Whether the help popup covers the screen.
+    show_help: bool,
     pub state: AppState<'a>,
     pub config: AppConfig,
 }
 
 impl<'a> App<'a> {
-    pub fn new(io_tx: tokio::sync::mpsc::Sender<IoEvent>, image_mode: ImageMode) -> Self {
+    pub fn new(io_tx: tokio::sync::mpsc::Sender<IoEvent>, config: AppConfig) -> Self {
         let actions = vec![Action::Quit].into();
+        let view_actions = Actions::default();
+        let adjust_actions = Actions::default();
         let is_loading = false;
+        let show_help = false;
         let state = AppState::default();
-        let config = AppConfig { image_mode };
 
         Self {
             io_tx,
             actions,
+            view_actions,
+            adjust_actions,
             is_loading,
+            show_help,
             state,
             config,
         }
     }
 
     pub async fn do_action(&mut self, key: Key) -> AppReturn {
+        if self.show_help {
+            match self.actions().find(key) {
+                Some(Action::Quit) => return AppReturn::Exit,
+                Some(Action::Help) => self.show_help = false,
+                _ if key == Key::Esc => self.show_help = false,
+                _ => {}
+            }
+            return AppReturn::Continue;
+        }
+
         match self.state.get_app_mode() {
-            AppMode::Normal => {
-                if let Some(action) = self.actions.find(key) {
+            AppMode::Normal | AppMode::View | AppMode::Adjust => {
+                if let Some(action) = self.actions().find(key).copied() {
                     match action {
                         Action::Quit => AppReturn::Exit,
+                        Action::Help => {
+                            self.show_help = true;
+                            AppReturn::Continue
+                        }
                         Action::Increment => {
                             self.state.increment_index();
                             AppReturn::Continue
@@ -65,6 +104,8 @@ impl<'a> App<'a> {
                             AppReturn::Continue
                         }
                         Action::Show => {
+                            self.state.set_viewport(Viewport::default());
+                            self.state.set_transform(self.config.transform);
                             self.dispatch(IoEvent::LoadImage).await;
                             AppReturn::Continue
                         }
@@ -72,6 +113,233 @@ impl<'a> App<'a> {
                             self.state.set_app_mode(AppMode::Search);
                             AppReturn::Continue
                         }
+                        Action::SwitchRenderer => {
+                            self.config.renderer = self.config.renderer.next(self.config.protocol);
+                            if self.state.get_current_image_info().is_some() {
+                                self.dispatch(IoEvent::RenderImage).await;
+                            }
+                            AppReturn::Continue
+                        }
+                        Action::PauseAnimation => {
+                            self.state.toggle_animation();
+                            AppReturn::Continue
+                        }
+                        Action::NextFrame => {
+                            self.state.step_frame(true);
+                            AppReturn::Continue
+                        }
+                        Action::PrevFrame => {
+                            self.state.step_frame(false);
+                            AppReturn::Continue
+                        }
+                        Action::ToneMap => {
+                            self.config.tone_map.operator = self.config.tone_map.operator.next();
+                            self.render_hdr().await;
+                            AppReturn::Continue
+                        }
+                        Action::IncreaseExposure => {
+                            self.config.tone_map.exposure += tone_map::EXPOSURE_STEP;
+                            self.render_hdr().await;
+                            AppReturn::Continue
+                        }
+                        Action::DecreaseExposure => {
+                            self.config.tone_map.exposure -= tone_map::EXPOSURE_STEP;
+                            self.render_hdr().await;
+                            AppReturn::Continue
+                        }
+                        Action::View => {
+                            if self.state.get_current_image_info().is_some() {
+                                self.state.set_app_mode(AppMode::View);
+                            }
+                            AppReturn::Continue
+                        }
+                        Action::ZoomIn => {
+                            self.update_viewport(Viewport::zoom_in).await;
+                            AppReturn::Continue
+                        }
+                        Action::ZoomOut => {
+                            self.update_viewport(Viewport::zoom_out).await;
+                            AppReturn::Continue
+                        }
+                        Action::ActualSize => {
+                            self.update_viewport(|viewport, _, _| viewport.zoom = Zoom::Scale(1.0))
+                                .await;
+                            AppReturn::Continue
+                        }
+                        Action::FitWidth => {
+                            self.update_viewport(|viewport, _, _| viewport.zoom = Zoom::FitWidth)
+                                .await;
+                            AppReturn::Continue
+                        }
+                        Action::FitHeight => {
+                            self.update_viewport(|viewport, _, _| viewport.zoom = Zoom::FitHeight)
+                                .await;
+                            AppReturn::Continue
+                        }
+                        Action::Fit => {
+                            self.update_viewport(|viewport, _, _| *viewport = Viewport::default())
+                                .await;
+                            AppReturn::Continue
+                        }
+                        Action::PanLeft => {
+                            self.update_viewport(|viewport, dims, res| {
+                                viewport.pan(-1, 0, dims, res)
+                            })
+                            .await;
+                            AppReturn::Continue
+                        }
+                        Action::PanDown => {
+                            self.update_viewport(|viewport, dims, res| {
+                                viewport.pan(0, 1, dims, res)
+                            })
+                            .await;
+                            AppReturn::Continue
+                        }
+                        Action::PanUp => {
+                            self.update_viewport(|viewport, dims, res| {
+                                viewport.pan(0, -1, dims, res)
+                            })
+                            .await;
+                            AppReturn::Continue
+                        }
+                        Action::PanRight => {
+                            self.update_viewport(|viewport, dims, res| {
+                                viewport.pan(1, 0, dims, res)
+                            })
+                            .await;
+                            AppReturn::Continue
+                        }
+                        Action::LeaveView => {
+                            self.state.set_app_mode(AppMode::Normal);
+                            AppReturn::Continue
+                        }
+                        Action::FitMode => {
+                            let scaling = &mut self.config.render_options.scaling;
+                            scaling.fit = scaling.fit.next();
+                            self.render().await;
+                            AppReturn::Continue
+                        }
+                        Action::Filter => {
+                            let scaling = &mut self.config.render_options.scaling;
+                            scaling.filter = scaling.filter.next();
+                            self.render().await;
+                            AppReturn::Continue
+                        }
+                        Action::Backdrop => {
+                            let transparency = &mut self.config.render_options.transparency;
+                            transparency.backdrop = transparency.backdrop.next();
+                            self.render().await;
+                            AppReturn::Continue
+                        }
+                        Action::ColorMode => {
+                            self.config.image_mode = self.config.image_mode.next();
+                            self.render().await;
+                            AppReturn::Continue
+                        }
+                        Action::Adjust => {
+                            if self.state.get_current_image_info().is_some() {
+                                self.state.set_app_mode(AppMode::Adjust);
+                            }
+                            AppReturn::Continue
+                        }
+                        Action::IncreaseBrightness => {
+                            self.update_adjustments(|a| {
+                                a.brightness =
+                                    Adjustments::step(a.brightness, adjust::STEP, -1.0, 1.0)
+                            })
+                            .await;
+                            AppReturn::Continue
+                        }
+                        Action::DecreaseBrightness => {
+                            self.update_adjustments(|a| {
+                                a.brightness =
+                                    Adjustments::step(a.brightness, -adjust::STEP, -1.0, 1.0)
+                            })
+                            .await;
+                            AppReturn::Continue
+                        }
+                        Action::IncreaseContrast => {
+                            self.update_adjustments(|a| {
+                                a.contrast = Adjustments::step(a.contrast, adjust::STEP, -1.0, 1.0)
+                            })
+                            .await;
+                            AppReturn::Continue
+                        }
+                        Action::DecreaseContrast => {
+                            self.update_adjustments(|a| {
+                                a.contrast = Adjustments::step(a.contrast, -adjust::STEP, -1.0, 1.0)
+                            })
+                            .await;
+                            AppReturn::Continue
+                        }
+                        Action::IncreaseGamma => {
+                            self.update_adjustments(|a| {
+                                a.gamma = (a.gamma * adjust::GAMMA_STEP).min(10.0)
+                            })
+                            .await;
+                            AppReturn::Continue
+                        }
+                        Action::DecreaseGamma => {
+                            self.update_adjustments(|a| {
+                                a.gamma = (a.gamma / adjust::GAMMA_STEP).max(0.1)
+                            })
+                            .await;
+                            AppReturn::Continue
+                        }
+                        Action::IncreaseSaturation => {
+                            self.update_adjustments(|a| {
+                                a.saturation =
+                                    Adjustments::step(a.saturation, adjust::STEP, 0.0, 3.0)
+                            })
+                            .await;
+                            AppReturn::Continue
+                        }
+                        Action::DecreaseSaturation => {
+                            self.update_adjustments(|a| {
+                                a.saturation =
+                                    Adjustments::step(a.saturation, -adjust::STEP, 0.0, 3.0)
+                            })
+                            .await;
+                            AppReturn::Continue
+                        }
+                        Action::Invert => {
+                            self.update_adjustments(|a| a.invert = !a.invert).await;
+                            AppReturn::Continue
+                        }
+                        Action::Sepia => {
+                            self.update_adjustments(|a| a.sepia = !a.sepia).await;
+                            AppReturn::Continue
+                        }
+                        Action::Levels => {
+                            self.update_adjustments(|a| a.levels = a.levels.next())
+                                .await;
+                            AppReturn::Continue
+                        }
+                        Action::ResetAdjustments => {
+                            self.update_adjustments(|a| *a = Adjustments::default())
+                                .await;
+                            AppReturn::Continue
+                        }
+                        Action::LeaveAdjust => {
+                            self.state.set_app_mode(AppMode::Normal);
+                            AppReturn::Continue
+                        }
+                        Action::RotateLeft => {
+                            self.update_transform(Transform::rotate_left).await;
+                            AppReturn::Continue
+                        }
+                        Action::RotateRight => {
+                            self.update_transform(Transform::rotate_right).await;
+                            AppReturn::Continue
+                        }
+                        Action::FlipHorizontal => {
+                            self.update_transform(|t| t.flip(Flip::Horizontal)).await;
+                            AppReturn::Continue
+                        }
+                        Action::FlipVertical => {
+                            self.update_transform(|t| t.flip(Flip::Vertical)).await;
+                            AppReturn::Continue
+                        }
                     }
                 } else {
                     AppReturn::Continue
@@ -82,7 +350,7 @@ impl<'a> App<'a> {
 
                 match key {
                     Key::Backspace => {
-                        if search_term.len() > 0 {
+                        if !search_term.is_empty() {
                             self.state
                                 .set_search_term(search_term[..search_term.len() - 1].to_string())
                         }
@@ -100,12 +368,92 @@ impl<'a> App<'a> {
                 }
 
                 self.state.filter_paths();
-                return AppReturn::Continue;
+                AppReturn::Continue
             }
         }
     }
 
+    /// Renders the current image again after the tone mapping changed.
+    async fn render_hdr(&mut self) {
+        if self
+            .state
+            .get_current_image_info()
+            .is_some_and(|info| info.hdr)
+        {
+            self.dispatch(IoEvent::RenderImage).await;
+        }
+    }
+
+    /// Renders the current image again after the render options changed.
+    async fn render(&mut self) {
+        if self.state.get_current_image_info().is_some() {
+            self.dispatch(IoEvent::RenderImage).await;
+        }
+    }
+
+    /// Changes the color adjustments and renders the current image again.
+    async fn update_adjustments<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut Adjustments),
+    {
+        f(&mut self.config.adjustments);
+        self.render().await;
+    }
+
+    /// Changes the part of the current image shown and renders it again.
+    async fn update_viewport<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut Viewport, (u32, u32), (u32, u32)),
+    {
+        let (Some(info), Some(resolution)) =
+            (self.state.get_current_image_info(), self.resolution())
+        else {
+            return;
+        };
+
+        let dimensions = self.state.get_image_transform().dimensions(info.dimensions);
+        let mut viewport = self.state.get_viewport();
+        f(&mut viewport, dimensions, resolution);
+        if viewport != self.state.get_viewport() {
+            self.state.set_viewport(viewport);
+            self.dispatch(IoEvent::RenderImage).await;
+        }
+    }
+
+    /// Rotates or flips the current image and renders it again.
+    async fn update_transform<F>(&mut self, f: F)
+    where
+        F: FnOnce(Transform) -> Transform,
+    {
+        if self.state.get_current_image_info().is_none() {
+            return;
+        }
+
+        self.state.set_transform(f(self.state.get_transform()));
+        // The visible area does not match the turned image.
+        self.state.set_viewport(Viewport::default());
+        self.dispatch(IoEvent::RenderImage).await;
+    }
+
+    /// Pixels the current renderer can show in the image panel.
+    pub fn resolution(&self) -> Option<(u32, u32)> {
+        let (cols, rows) = self.image_cells()?;
+        let renderer = self.config.renderer.renderer(&self.config.render_options);
+        Some(renderer.resolution(cols, rows))
+    }
+
+    /// Columns and rows the image is rendered in, inside the borders of the
+    /// image block.
+    pub fn image_cells(&self) -> Option<(u32, u32)> {
+        let term_size = self.state.get_term_size()?;
+        Some((
+            term_size.width.saturating_sub(2),
+            term_size.height.saturating_sub(2),
+        ))
+    }
+
     pub async fn update_on_tick(&mut self) -> AppReturn {
+        self.state.advance_frame();
         AppReturn::Continue
     }
 
@@ -117,14 +465,22 @@ impl<'a> App<'a> {
     }
 
     pub fn actions(&self) -> &Actions {
-        &self.actions
+        match self.state.get_app_mode() {
+            AppMode::View => &self.view_actions,
+            AppMode::Adjust => &self.adjust_actions,
+            AppMode::Normal | AppMode::Search => &self.actions,
+        }
+    }
+
+    pub fn is_help_shown(&self) -> bool {
+        self.show_help
     }
 
-    pub fn state(&self) -> &AppState {
+    pub fn state(&self) -> &AppState<'_> {
         &self.state
     }
 
-    pub fn state_mut(&'a mut self) -> &'a mut AppState {
+    pub fn state_mut(&'a mut self) -> &'a mut AppState<'a> {
         &mut self.state
     }
 
@@ -135,13 +491,65 @@ impl<'a> App<'a> {
     pub fn initialized(&mut self, path: &str) {
         self.actions = vec![
             Action::Quit,
+            Action::Help,
             Action::Increment,
             Action::Decrement,
             Action::Show,
             Action::Search,
+            Action::SwitchRenderer,
+            Action::PauseAnimation,
+            Action::NextFrame,
+            Action::PrevFrame,
+            Action::ToneMap,
+            Action::IncreaseExposure,
+            Action::DecreaseExposure,
+            Action::View,
+            Action::RotateLeft,
+            Action::RotateRight,
+            Action::FlipHorizontal,
+            Action::FlipVertical,
+            Action::FitMode,
+            Action::Filter,
+            Action::Backdrop,
+            Action::ColorMode,
+            Action::Adjust,
+        ]
+        .into();
+        self.view_actions = vec![
+            Action::Quit,
+            Action::Help,
+            Action::ZoomIn,
+            Action::ZoomOut,
+            Action::ActualSize,
+            Action::FitWidth,
+            Action::FitHeight,
+            Action::Fit,
+            Action::PanLeft,
+            Action::PanDown,
+            Action::PanUp,
+            Action::PanRight,
+            Action::LeaveView,
+        ]
+        .into();
+        self.adjust_actions = vec![
+            Action::Quit,
+            Action::Help,
+            Action::IncreaseBrightness,
+            Action::DecreaseBrightness,
+            Action::IncreaseContrast,
+            Action::DecreaseContrast,
+            Action::IncreaseGamma,
+            Action::DecreaseGamma,
+            Action::IncreaseSaturation,
+            Action::DecreaseSaturation,
+            Action::Invert,
+            Action::Sepia,
+            Action::Levels,
+            Action::ResetAdjustments,
+            Action::LeaveAdjust,
         ]
         .into();
-        self.state = AppState::initialized(path);
+        self.state = AppState::initialized(path, &self.config.file_filter);
     }
 
     pub fn loaded(&mut self) {
@@ -1,24 +1,47 @@
-use crate::utils;
+use crate::{
+    decoder::Format,
+    graphics::Graphic,
+    transform::Transform,
+    utils::{self, FileFilter, ImageFile},
+    viewport::Viewport,
+};
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
-use std::path::PathBuf;
-use tui::text::Line;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use tui::{layout::Rect, text::Line};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum AppMode {
     Normal,
     Search,
+    /// Zooming and panning the current image.
+    View,
+    /// Adjusting the colors of the current image.
+    Adjust,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
+#[allow(clippy::large_enum_variant)]
 pub enum AppState<'a> {
+    #[default]
     Init,
     Initialized {
-        paths: Vec<PathBuf>,
+        /// Files matching the search term.
+        paths: Vec<ImageFile>,
+        all_paths: Vec<ImageFile>,
         path: String,
         selected_index: usize,
         term_size: Option<TermSize>,
-        current_image: Option<Vec<Line<'a>>>,
+        current_image: Option<CurrentImage<'a>>,
+        /// Why the selected image could not be shown.
+        image_error: Option<String>,
+        image_area: Option<Rect>,
         current_image_info: Option<ImageInfo>,
+        viewport: Viewport,
+        /// Rotation and flip applied to the current image after its orientation.
+        transform: Transform,
         search_term: String,
         app_mode: AppMode,
     },
@@ -30,29 +53,75 @@ pub struct TermSize {
     pub height: u32,
 }
 
+/// A frame rendered for the image panel.
+#[derive(Debug, Clone)]
+pub struct RenderedFrame<'a> {
+    pub lines: Vec<Line<'a>>,
+    pub graphic: Option<Graphic>,
+    pub delay: Duration,
+}
+
+/// The frames of the image shown in the panel and the animation playback.
+#[derive(Debug, Clone)]
+pub struct CurrentImage<'a> {
+    pub frames: Vec<RenderedFrame<'a>>,
+    pub index: usize,
+    pub paused: bool,
+    pub shown_at: Instant,
+    /// Whether the other frames are still rendered with older settings, which
+    /// holds the animation on the current one.
+    pub stale: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct ImageInfo {
     pub name: String,
     pub size: u64,
     pub dimensions: (u32, u32),
+    pub format: Format,
+    /// Whether the image is tone mapped.
+    pub hdr: bool,
+    pub layers: Vec<String>,
+    /// How the image is displayed upright, from its metadata.
+    pub orientation: Transform,
+}
+
+/// Position in the frames of an animation, or in the pages of a document.
+#[derive(Debug, Clone, Copy)]
+pub struct FrameCounter {
+    /// 1-based.
+    pub index: usize,
+    pub count: usize,
+    pub animated: bool,
+    pub paused: bool,
 }
 
 impl<'a> AppState<'a> {
-    pub fn initialized(path: &str) -> Self {
-        let paths = utils::get_image_paths(path);
+    pub fn initialized(path: &str, filter: &FileFilter) -> Self {
+        let all_paths = utils::get_image_paths(path, filter);
+        let paths = all_paths.clone();
         let selected_index = 0;
         let current_image = None;
+        let image_error = None;
+        let image_area = None;
         let term_size = None;
         let current_image_info = None;
+        let viewport = Viewport::default();
+        let transform = Transform::default();
         let search_term = "".to_string();
         let app_mode = AppMode::Normal;
         Self::Initialized {
             paths,
+            all_paths,
             path: path.to_string(),
             selected_index,
             term_size,
             current_image,
+            image_error,
+            image_area,
             current_image_info,
+            viewport,
+            transform,
             search_term,
             app_mode,
         }
@@ -62,7 +131,7 @@ impl<'a> AppState<'a> {
         matches!(self, &Self::Initialized { .. })
     }
 
-    pub fn get_paths(&self) -> Vec<PathBuf> {
+    pub fn get_paths(&self) -> Vec<ImageFile> {
         if let Self::Initialized { paths, .. } = self {
             paths.clone()
         } else {
@@ -72,31 +141,19 @@ impl<'a> AppState<'a> {
 
     pub fn filter_paths(&mut self) {
         if let Self::Initialized {
-            paths: self_paths,
-            path,
+            paths,
+            all_paths,
             search_term,
             ..
         } = self
         {
             let matcher = SkimMatcherV2::default();
 
-            // TODO: Load the image paths only once and filter it there.
-            let paths = utils::get_image_paths(path)
-                .into_iter()
-                .filter(|path| {
-                    let file_name = path
-                        .file_name()
-                        .unwrap()
-                        .to_os_string()
-                        .into_string()
-                        .unwrap();
-                    match matcher.fuzzy_match(&file_name, search_term) {
-                        Some(_) => true,
-                        None => false,
-                    }
-                })
+            *paths = all_paths
+                .iter()
+                .filter(|file| matcher.fuzzy_match(&file.name(), search_term).is_some())
+                .cloned()
                 .collect();
-            *self_paths = paths;
         }
     }
 
@@ -105,13 +162,27 @@ impl<'a> AppState<'a> {
             if paths.is_empty() {
                 None
             } else {
-                Some(paths[index].clone())
+                Some(paths[index].path.clone())
             }
         } else {
             None
         }
     }
 
+    /// Marks the file at `path` after it failed to decode.
+    pub fn mark_undecodable(&mut self, path: &Path) {
+        if let Self::Initialized {
+            paths, all_paths, ..
+        } = self
+        {
+            for file in paths.iter_mut().chain(all_paths.iter_mut()) {
+                if file.path == path {
+                    file.format = None;
+                }
+            }
+        }
+    }
+
     pub fn increment_index(&mut self) {
         if let Self::Initialized {
             selected_index,
@@ -166,15 +237,217 @@ impl<'a> AppState<'a> {
         }
     }
 
-    pub fn set_current_image(&mut self, img: Vec<Line<'a>>) {
-        if let Self::Initialized { current_image, .. } = self {
-            *current_image = Some(img);
+    pub fn set_current_image(&mut self, frames: Vec<RenderedFrame<'a>>) {
+        if let Self::Initialized {
+            current_image,
+            image_error,
+            ..
+        } = self
+        {
+            *current_image = Some(CurrentImage {
+                frames,
+                index: 0,
+                paused: false,
+                shown_at: Instant::now(),
+                stale: false,
+            });
+            *image_error = None;
+        }
+    }
+
+    /// Replaces the current image with the error that prevented showing the
+    /// selected one.
+    pub fn set_image_error(&mut self, error: String) {
+        if let Self::Initialized {
+            current_image,
+            current_image_info,
+            image_error,
+            ..
+        } = self
+        {
+            *current_image = None;
+            *current_image_info = None;
+            *image_error = Some(error);
+        }
+    }
+
+    pub fn get_image_error(&self) -> Option<&str> {
+        if let Self::Initialized { image_error, .. } = self {
+            image_error.as_deref()
+        } else {
+            None
+        }
+    }
+
+    /// Replaces the frames of the current image with ones rendered again,
+    /// keeping the playback position.
+    pub fn update_current_image(&mut self, frames: Vec<RenderedFrame<'a>>) {
+        if let Self::Initialized {
+            current_image: Some(current_image),
+            ..
+        } = self
+        {
+            if current_image.frames.len() == frames.len() {
+                current_image.frames = frames;
+                current_image.stale = false;
+                return;
+            }
+        }
+
+        self.set_current_image(frames);
+    }
+
+    /// Replaces the frame at `index` with one rendered again, holding the
+    /// animation until the other frames are replaced too.
+    pub fn update_current_frame(&mut self, index: usize, frame: RenderedFrame<'a>) {
+        if let Self::Initialized {
+            current_image: Some(current_image),
+            ..
+        } = self
+        {
+            if let Some(current_frame) = current_image.frames.get_mut(index) {
+                *current_frame = frame;
+                current_image.stale = true;
+            }
+        }
+    }
+
+    /// Returns the index of the shown frame.
+    pub fn get_frame_index(&self) -> Option<usize> {
+        if let Self::Initialized {
+            current_image: Some(current_image),
+            ..
+        } = self
+        {
+            Some(current_image.index)
+        } else {
+            None
         }
     }
 
     pub fn get_current_image(&self) -> Option<Vec<Line<'a>>> {
-        if let Self::Initialized { current_image, .. } = self {
-            current_image.clone()
+        if let Self::Initialized {
+            current_image: Some(current_image),
+            ..
+        } = self
+        {
+            Some(current_image.frames[current_image.index].lines.clone())
+        } else {
+            None
+        }
+    }
+
+    pub fn get_current_graphic(&self) -> Option<&Graphic> {
+        if let Self::Initialized {
+            current_image: Some(current_image),
+            ..
+        } = self
+        {
+            current_image.frames[current_image.index].graphic.as_ref()
+        } else {
+            None
+        }
+    }
+
+    /// Whether the current image is an animation being played.
+    pub fn is_animating(&self) -> bool {
+        if let Self::Initialized {
+            current_image: Some(current_image),
+            ..
+        } = self
+        {
+            !current_image.paused
+                && !current_image.stale
+                && current_image.frames.len() > 1
+                && current_image
+                    .frames
+                    .iter()
+                    .any(|frame| !frame.delay.is_zero())
+        } else {
+            false
+        }
+    }
+
+    /// Shows the next frame once the current one has been displayed long enough.
+    pub fn advance_frame(&mut self) {
+        if let Self::Initialized {
+            current_image: Some(current_image),
+            ..
+        } = self
+        {
+            let delay = current_image.frames[current_image.index].delay;
+            // Pages have no delay and are only stepped through.
+            if current_image.paused
+                || current_image.stale
+                || delay.is_zero()
+                || current_image.frames.len() < 2
+                || current_image.shown_at.elapsed() < delay
+            {
+                return;
+            }
+
+            current_image.index = (current_image.index + 1) % current_image.frames.len();
+            current_image.shown_at = Instant::now();
+        }
+    }
+
+    pub fn toggle_animation(&mut self) {
+        if let Self::Initialized {
+            current_image: Some(current_image),
+            ..
+        } = self
+        {
+            current_image.paused = !current_image.paused;
+            current_image.shown_at = Instant::now();
+        }
+    }
+
+    /// Pauses the animation and moves one frame forward or back.
+    pub fn step_frame(&mut self, forward: bool) {
+        if let Self::Initialized {
+            current_image: Some(current_image),
+            ..
+        } = self
+        {
+            let len = current_image.frames.len();
+            current_image.paused = true;
+            current_image.index = if forward {
+                (current_image.index + 1) % len
+            } else {
+                (current_image.index + len - 1) % len
+            };
+        }
+    }
+
+    /// Returns the position in the frames, for images with several ones only.
+    pub fn get_frame_counter(&self) -> Option<FrameCounter> {
+        if let Self::Initialized {
+            current_image: Some(current_image),
+            ..
+        } = self
+        {
+            if current_image.frames.len() > 1 {
+                return Some(FrameCounter {
+                    index: current_image.index + 1,
+                    count: current_image.frames.len(),
+                    animated: current_image.frames.iter().any(|f| !f.delay.is_zero()),
+                    paused: current_image.paused,
+                });
+            }
+        }
+
+        None
+    }
+
+    pub fn set_image_area(&mut self, area: Rect) {
+        if let Self::Initialized { image_area, .. } = self {
+            *image_area = Some(area);
+        }
+    }
+
+    pub fn get_image_area(&self) -> Option<Rect> {
+        if let Self::Initialized { image_area, .. } = self {
+            *image_area
         } else {
             None
         }
@@ -200,6 +473,49 @@ impl<'a> AppState<'a> {
         }
     }
 
+    pub fn set_viewport(&mut self, arg: Viewport) {
+        if let Self::Initialized { viewport, .. } = self {
+            *viewport = arg;
+        }
+    }
+
+    pub fn get_viewport(&self) -> Viewport {
+        if let Self::Initialized { viewport, .. } = self {
+            *viewport
+        } else {
+            Viewport::default()
+        }
+    }
+
+    pub fn set_transform(&mut self, arg: Transform) {
+        if let Self::Initialized { transform, .. } = self {
+            *transform = arg;
+        }
+    }
+
+    pub fn get_transform(&self) -> Transform {
+        if let Self::Initialized { transform, .. } = self {
+            *transform
+        } else {
+            Transform::default()
+        }
+    }
+
+    /// Returns how the current image is displayed: its orientation followed by
+    /// the rotation and flip.
+    pub fn get_image_transform(&self) -> Transform {
+        if let Self::Initialized {
+            current_image_info: Some(info),
+            transform,
+            ..
+        } = self
+        {
+            info.orientation.then(*transform)
+        } else {
+            Transform::default()
+        }
+    }
+
     pub fn get_search_term(&self) -> &str {
         let Self::Initialized { search_term, .. } = self else {
             return "";
@@ -225,9 +541,3 @@ impl<'a> AppState<'a> {
         app_mode.clone()
     }
 }
-
-impl<'a> Default for AppState<'a> {
-    fn default() -> Self {
-        Self::Init
-    }
-}
@@ -0,0 +1,80 @@
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Rect},
+    text::Span,
+    widgets::{Block, Cell, Row, Table},
+    Frame,
+};
+
+/// Draws key and value pairs from top to bottom in as many columns as needed
+/// to fit inside `block`.
+///
+/// Keys keep their width while values share the rest, the longest ones being
+/// truncated first.
+pub fn draw<B>(rect: &mut Frame<B>, area: Rect, block: Block, entries: Vec<(Span, Span)>)
+where
+    B: Backend,
+{
+    let inner = block.inner(area);
+    let (rows, width) = (inner.height.max(1) as usize, inner.width);
+    let count = entries.len().div_ceil(rows);
+    let columns: Vec<Vec<(Span, Span)>> =
+        entries.chunks(rows).map(|column| column.to_vec()).collect();
+
+    let key_widths: Vec<u16> = columns
+        .iter()
+        .map(|column| column.iter().map(|(key, _)| key.width()).max().unwrap_or(0) as u16)
+        .collect();
+    let value_widths: Vec<u16> = columns
+        .iter()
+        .map(|column| {
+            column
+                .iter()
+                .map(|(_, value)| value.width())
+                .max()
+                .unwrap_or(0) as u16
+        })
+        .collect();
+
+    let spacing = (2 * count).saturating_sub(1) as u16;
+    let available = width.saturating_sub(key_widths.iter().sum::<u16>() + spacing);
+    let value_widths = share(&value_widths, available);
+
+    let rows: Vec<Row> = (0..rows)
+        .filter(|row| *row < columns.first().map_or(0, Vec::len))
+        .map(|row| {
+            Row::new(columns.iter().flat_map(|column| match column.get(row) {
+                Some((key, value)) => [Cell::from(key.clone()), Cell::from(value.clone())],
+                None => [Cell::from(""), Cell::from("")],
+            }))
+        })
+        .collect();
+
+    let widths: Vec<Constraint> = key_widths
+        .iter()
+        .zip(value_widths)
+        .flat_map(|(key, value)| [Constraint::Length(*key), Constraint::Length(value)])
+        .collect();
+
+    let table = Table::new(rows)
+        .block(block)
+        .widths(&widths)
+        .column_spacing(1);
+    rect.render_widget(table, area);
+}
+
+/// Splits `available` cells between `wanted` widths, giving the narrow ones
+/// all they want first.
+fn share(wanted: &[u16], available: u16) -> Vec<u16> {
+    let mut order: Vec<usize> = (0..wanted.len()).collect();
+    order.sort_by_key(|i| wanted[*i]);
+
+    let mut widths = vec![0; wanted.len()];
+    let mut left = available;
+    for (n, i) in order.iter().enumerate() {
+        let fair = left / (wanted.len() - n) as u16;
+        widths[*i] = wanted[*i].min(fair);
+        left -= widths[*i];
+    }
+    widths
+}
@@ -1,32 +1,68 @@
-use crate::app::Actions;
+use super::columns;
+use crate::app::{actions::Action, state::AppMode, Actions};
 use tui::{
-    layout::Constraint,
+    backend::Backend,
+    layout::Rect,
     style::{Color, Style},
-    text::Span,
-    widgets::{Block, BorderType, Borders, Cell, Row, Table},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+    Frame,
 };
 
-pub fn draw(actions: &Actions) -> Table {
+/// Shows the current mode and how to open the help.
+pub fn draw<'a>(mode: AppMode) -> Paragraph<'a> {
     let key_style = Style::default().fg(Color::LightCyan);
     let help_style = Style::default().fg(Color::Gray);
 
-    let mut rows = vec![];
-    for action in actions.actions().iter() {
-        let keys: Vec<String> = action.keys().iter().map(|k| k.to_string()).collect();
-        let key = keys.join(", ");
-        let row = Row::new(vec![
-            Cell::from(Span::styled(key, key_style)),
-            Cell::from(Span::styled(action.to_string(), help_style)),
-        ]);
-        rows.push(row);
-    }
+    let mode = match mode {
+        AppMode::Normal => "Normal",
+        AppMode::Search => "Search",
+        AppMode::View => "Zoom and pan",
+        AppMode::Adjust => "Adjust",
+    };
+    let keys: Vec<String> = Action::Help.keys().iter().map(|k| k.to_string()).collect();
 
-    Table::new(rows)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_type(BorderType::Plain),
-        )
-        .widths(&[Constraint::Length(30), Constraint::Percentage(70)])
-        .column_spacing(1)
+    Paragraph::new(Line::from(vec![
+        Span::styled(keys.join(", "), key_style),
+        Span::raw(" "),
+        Span::styled(Action::Help.to_string(), help_style),
+    ]))
+    .block(
+        Block::default()
+            .title(mode)
+            .borders(Borders::ALL)
+            .border_type(BorderType::Plain),
+    )
+}
+
+/// Draws the keys of `actions` over `area`.
+pub fn draw_popup<B>(rect: &mut Frame<B>, area: Rect, actions: &Actions)
+where
+    B: Backend,
+{
+    let key_style = Style::default().fg(Color::LightCyan);
+    let help_style = Style::default().fg(Color::Gray);
+
+    let entries = actions
+        .actions()
+        .iter()
+        .map(|action| {
+            let keys: Vec<String> = action.keys().iter().map(|k| k.to_string()).collect();
+            (
+                Span::styled(keys.join(", "), key_style),
+                Span::styled(action.to_string(), help_style),
+            )
+        })
+        .collect();
+
+    rect.render_widget(Clear, area);
+    columns::draw(
+        rect,
+        area,
+        Block::default()
+            .title("Help")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Plain),
+        entries,
+    );
 }
@@ -2,15 +2,27 @@ use crate::app::state::AppState;
 use tui::{
     layout::Alignment,
     style::{Color, Style},
-    widgets::{Block, BorderType, Borders, Paragraph},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
 };
 
 pub fn draw<'a>(state: &'a AppState) -> Paragraph<'a> {
-    let result = if let Some(current_image) = state.get_current_image() {
-        current_image
-    } else {
-        vec![]
-    };
+    if let Some(error) = state.get_image_error() {
+        return Paragraph::new(Line::from(Span::styled(
+            error.to_string(),
+            Style::default().fg(Color::LightRed),
+        )))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::White))
+                .border_type(BorderType::Plain),
+        )
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    }
+
+    let result = state.get_current_image().unwrap_or_default();
 
     Paragraph::new(result)
         .block(
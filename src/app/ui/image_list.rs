@@ -4,18 +4,20 @@ use tui::{
     widgets::{Block, BorderType, Borders, List, ListItem},
 };
 
+/// Prefix of the files that cannot be decoded.
+const UNDECODABLE_MARKER: char = '✗';
+
 pub fn draw<'a>(state: &AppState) -> List<'a> {
     let list_items: Vec<ListItem> = state
         .get_paths()
         .iter()
         .map(|img| {
-            ListItem::new(
-                img.file_name()
-                    .unwrap()
-                    .to_os_string()
-                    .into_string()
-                    .unwrap(),
-            )
+            if img.is_decodable() {
+                ListItem::new(img.name())
+            } else {
+                ListItem::new(format!("{} {}", UNDECODABLE_MARKER, img.name()))
+                    .style(Style::default().fg(Color::DarkGray))
+            }
         })
         .collect();
 
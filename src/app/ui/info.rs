@@ -1,48 +1,133 @@
-use crate::app::state::AppState;
+use super::columns;
+use crate::{
+    app::{state::AppState, AppConfig},
+    viewport::Zoom,
+};
 use byte_unit::Byte;
 use tui::{
-    layout::Constraint,
+    backend::Backend,
+    layout::Rect,
     style::{Color, Style},
     text::Span,
-    widgets::{Block, BorderType, Borders, Cell, Row, Table},
+    widgets::{Block, BorderType, Borders},
+    Frame,
 };
 
-pub fn draw<'a>(state: &AppState) -> Table<'a> {
+/// Draws the details of the current image, leaving out the ones that do not
+/// apply to it.
+pub fn draw<B>(
+    rect: &mut Frame<B>,
+    area: Rect,
+    state: &AppState,
+    config: &AppConfig,
+    resolution: Option<(u32, u32)>,
+) where
+    B: Backend,
+{
     let key_style = Style::default().fg(Color::LightCyan);
     let value_style = Style::default().fg(Color::Gray);
 
-    let rows = if let Some(image_info) = state.get_current_image_info() {
+    let entries = if let Some(image_info) = state.get_current_image_info() {
         let size = Byte::from(image_info.size)
             .get_appropriate_unit(false)
             .to_string();
 
+        let (frame_key, frame) = match state.get_frame_counter() {
+            Some(counter) if !counter.animated => {
+                ("Page", format!("{}/{}", counter.index, counter.count))
+            }
+            Some(counter) if counter.paused => (
+                "Frame",
+                format!("{}/{} (paused)", counter.index, counter.count),
+            ),
+            Some(counter) => ("Frame", format!("{}/{}", counter.index, counter.count)),
+            None => ("Frame", "-".to_string()),
+        };
+
+        let tone_map = if image_info.hdr {
+            config.tone_map.to_string()
+        } else {
+            "-".to_string()
+        };
+
+        let zoom = match resolution {
+            Some(resolution) => {
+                let viewport = state.get_viewport();
+                let dimensions = state
+                    .get_image_transform()
+                    .dimensions(image_info.dimensions);
+                let zoom = match viewport.zoom {
+                    Zoom::Scale(_) => viewport.zoom.to_string(),
+                    _ => format!(
+                        "{} ({:.0}%)",
+                        viewport.zoom,
+                        viewport.scale(dimensions, resolution) * 100.0
+                    ),
+                };
+                // The offset of the visible area, once it is cropped.
+                let area = viewport.area(dimensions, resolution, dimensions);
+                if viewport.zoom == Zoom::Fit || (area.width, area.height) == dimensions {
+                    zoom
+                } else {
+                    format!("{} at {},{}", zoom, area.x, area.y)
+                }
+            }
+            None => "-".to_string(),
+        };
+
+        let transform = state.get_image_transform();
+        let transform = if image_info.orientation.is_identity() {
+            transform.to_string()
+        } else {
+            format!("{} (EXIF: {})", transform, image_info.orientation)
+        };
+
+        let layers = if image_info.layers.is_empty() {
+            "-".to_string()
+        } else {
+            image_info.layers.join(", ")
+        };
+
         vec![
-            Row::new(vec![
-                Cell::from(Span::styled("Name", key_style)),
-                Cell::from(Span::styled(image_info.name, value_style)),
-            ]),
-            Row::new(vec![
-                Cell::from(Span::styled("Dimensions", key_style)),
-                Cell::from(Span::styled(
-                    format!("{}x{}", image_info.dimensions.0, image_info.dimensions.1),
-                    value_style,
-                )),
-            ]),
-            Row::new(vec![
-                Cell::from(Span::styled("Size", key_style)),
-                Cell::from(Span::styled(size, value_style)),
-            ]),
+            ("Name", image_info.name),
+            (
+                "Dimensions",
+                format!("{}x{}", image_info.dimensions.0, image_info.dimensions.1),
+            ),
+            ("Format", image_info.format.to_string()),
+            ("Size", size),
+            ("Renderer", config.renderer.to_string()),
+            ("Color mode", config.image_mode.to_string()),
+            (frame_key, frame),
+            ("Scaling", config.render_options.scaling.to_string()),
+            ("Backdrop", config.render_options.transparency.to_string()),
+            ("Adjustments", config.adjustments.to_string()),
+            ("Zoom", zoom),
+            ("Transform", transform),
+            ("Tone map", tone_map),
+            ("Layers", layers),
         ]
     } else {
         vec![]
     };
 
-    Table::new(rows)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_type(BorderType::Plain),
-        )
-        .widths(&[Constraint::Length(15), Constraint::Percentage(85)])
-        .column_spacing(1)
+    let entries = entries
+        .into_iter()
+        .filter(|(_, value)| value != "-")
+        .map(|(key, value)| {
+            (
+                Span::styled(key, key_style),
+                Span::styled(value, value_style),
+            )
+        })
+        .collect();
+
+    columns::draw(
+        rect,
+        area,
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Plain),
+        entries,
+    );
 }
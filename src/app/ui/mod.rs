@@ -1,3 +1,4 @@
+mod columns;
 mod help;
 mod image;
 mod image_list;
@@ -11,12 +12,15 @@ use std::rc::Rc;
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
-    widgets::ListState,
+    widgets::{Block, Borders, ListState},
     Frame,
 };
 
 use super::state::AppMode;
 
+/// Height of the title, the mode and the image details.
+const HEADER_HEIGHT: u16 = 9;
+
 pub fn draw<B>(rect: &mut Frame<B>, app: &mut App)
 where
     B: Backend,
@@ -31,13 +35,18 @@ where
     let mut body_chunk = body_chunks[0];
 
     let title = title::draw();
-    let help = help::draw(app.actions());
-    let info = info::draw(app.state());
+    let help = help::draw(app.state.get_app_mode());
     let image_list = image_list::draw(app.state());
 
     rect.render_widget(title, header_chunks[0]);
     rect.render_widget(help, info_chunks[0]);
-    rect.render_widget(info, info_chunks[1]);
+    info::draw(
+        rect,
+        info_chunks[1],
+        app.state(),
+        &app.config,
+        app.resolution(),
+    );
 
     if app.state.get_app_mode() == AppMode::Search {
         let block = search::draw(app.state.get_search_term());
@@ -52,6 +61,8 @@ where
     let w = body_chunks[1].width as u32;
     let h = body_chunks[1].height as u32;
     app.state.set_term_size(w, h);
+    app.state
+        .set_image_area(Block::default().borders(Borders::ALL).inner(body_chunks[1]));
 
     if app.is_loading() && app.state.get_current_image().is_some() {
         let loading = loading::draw();
@@ -60,12 +71,16 @@ where
         let image = image::draw(app.state());
         rect.render_widget(image, body_chunks[1]);
     }
+
+    if app.is_help_shown() {
+        help::draw_popup(rect, main_chunks[1], app.actions());
+    }
 }
 
 fn main_layout(rect: Rect) -> Rc<[Rect]> {
     Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(9), Constraint::Percentage(90)])
+        .constraints([Constraint::Length(HEADER_HEIGHT), Constraint::Min(0)])
         .margin(1)
         .split(rect)
 }
@@ -73,7 +88,7 @@ fn main_layout(rect: Rect) -> Rc<[Rect]> {
 fn header_layout(rect: Rect) -> Rc<[Rect]> {
     Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+        .constraints([Constraint::Length(2), Constraint::Min(0)])
         .split(rect)
 }
 
@@ -0,0 +1,148 @@
+use crate::tone_map::srgb;
+use image::{Rgba, RgbaImage};
+use std::fmt::{self, Display};
+
+/// Color vision deficiencies that can be simulated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Deficiency {
+    /// No red cones.
+    Protanopia,
+    /// No green cones.
+    Deuteranopia,
+    /// No blue cones.
+    Tritanopia,
+    /// No color vision at all.
+    Achromatopsia,
+}
+
+impl Deficiency {
+    /// Matrix applied to linear RGB, from Machado et al. 2009 at full severity.
+    fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            Deficiency::Protanopia => [
+                [0.152_286, 1.052_583, -0.204_868],
+                [0.114_503, 0.786_281, 0.099_216],
+                [-0.003_882, -0.048_116, 1.051_998],
+            ],
+            Deficiency::Deuteranopia => [
+                [0.367_322, 0.860_646, -0.227_968],
+                [0.280_085, 0.672_501, 0.047_413],
+                [-0.011_820, 0.042_940, 0.968_881],
+            ],
+            Deficiency::Tritanopia => [
+                [1.255_528, -0.076_749, -0.178_779],
+                [-0.078_411, 0.930_809, 0.147_602],
+                [0.004_733, 0.691_367, 0.303_900],
+            ],
+            // The relative luminance of Rec. 709 in every channel.
+            Deficiency::Achromatopsia => [[0.2126, 0.7152, 0.0722]; 3],
+        }
+    }
+}
+
+/// Returns how `rgb` is seen with `deficiency`.
+pub fn simulate_color(rgb: [u8; 3], deficiency: Deficiency) -> [u8; 3] {
+    let linear = rgb.map(linear);
+    deficiency.matrix().map(|row| {
+        let v = row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2];
+        (srgb(v.clamp(0.0, 1.0)) * 255.0).round() as u8
+    })
+}
+
+/// Replaces the colors of `img` with how they are seen with `deficiency`,
+/// keeping the alpha channel.
+pub fn simulate(img: &mut RgbaImage, deficiency: Deficiency) {
+    for Rgba([r, g, b, _]) in img.pixels_mut() {
+        [*r, *g, *b] = simulate_color([*r, *g, *b], deficiency);
+    }
+}
+
+/// Decodes an sRGB channel to a linear value.
+fn linear(v: u8) -> f32 {
+    let v = v as f32 / 255.0;
+    if v <= 0.040_45 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl Display for Deficiency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            Deficiency::Protanopia => "protanopia",
+            Deficiency::Deuteranopia => "deuteranopia",
+            Deficiency::Tritanopia => "tritanopia",
+            Deficiency::Achromatopsia => "achromatopsia",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Deficiency; 4] = [
+        Deficiency::Protanopia,
+        Deficiency::Deuteranopia,
+        Deficiency::Tritanopia,
+        Deficiency::Achromatopsia,
+    ];
+
+    fn distance(a: [u8; 3], b: [u8; 3]) -> i32 {
+        (0..3).map(|i| (a[i] as i32 - b[i] as i32).abs()).sum()
+    }
+
+    #[test]
+    fn keeps_black_and_white() {
+        for deficiency in ALL {
+            assert_eq!(simulate_color([0, 0, 0], deficiency), [0, 0, 0]);
+            let white = simulate_color([255, 255, 255], deficiency);
+            assert!(
+                white.iter().all(|&c| c >= 254),
+                "{}: {:?}",
+                deficiency,
+                white
+            );
+        }
+    }
+
+    #[test]
+    fn achromatopsia_is_gray() {
+        for rgb in [[255, 0, 0], [30, 200, 90], [12, 34, 250]] {
+            let [r, g, b] = simulate_color(rgb, Deficiency::Achromatopsia);
+            assert!(r == g && g == b, "{:?}", [r, g, b]);
+        }
+    }
+
+    #[test]
+    fn confuses_red_and_green() {
+        let (red, green) = ([220, 40, 40], [40, 160, 40]);
+        for deficiency in [Deficiency::Protanopia, Deficiency::Deuteranopia] {
+            let simulated = distance(
+                simulate_color(red, deficiency),
+                simulate_color(green, deficiency),
+            );
+            assert!(simulated < distance(red, green) / 2, "{}", deficiency);
+        }
+    }
+
+    #[test]
+    fn tritanopia_confuses_blue_and_green() {
+        let (blue, green) = ([40, 90, 220], [40, 170, 130]);
+        let simulated = distance(
+            simulate_color(blue, Deficiency::Tritanopia),
+            simulate_color(green, Deficiency::Tritanopia),
+        );
+        assert!(simulated < distance(blue, green) / 2);
+    }
+
+    #[test]
+    fn keeps_alpha() {
+        let mut img = RgbaImage::from_pixel(2, 2, Rgba([200, 30, 60, 77]));
+        simulate(&mut img, Deficiency::Deuteranopia);
+        assert!(img.pixels().all(|p| p[3] == 77));
+        assert_ne!(img.get_pixel(0, 0).0[..3], [200, 30, 60]);
+    }
+}
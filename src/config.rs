@@ -0,0 +1,174 @@
+use crate::decoder::{self, external::ExternalDecoder};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Time given to external decoders that do not set a timeout.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Settings read from `config.toml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// External decoders by name.
+    pub decoders: BTreeMap<String, DecoderConfig>,
+}
+
+/// An external decoder, either its command or a table. The command is run by
+/// the shell with `{in}` replaced by the path of the file, and writes an image
+/// such as a PNG or a PPM to its stdout.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum DecoderConfig {
+    Command(String),
+    Table {
+        command: String,
+        /// Defaults to the name of the decoder.
+        #[serde(default)]
+        extensions: Vec<String>,
+        /// Text recognizing files regardless of their extension, such as `%PDF`.
+        magic: Option<String>,
+        /// Position of `magic` in the file.
+        #[serde(default)]
+        offset: usize,
+        /// In seconds.
+        timeout: Option<f64>,
+    },
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/picterm/config.toml`, or `~/.config/picterm/config.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        let dir = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(dir.join("picterm").join("config.toml"))
+    }
+
+    /// Reads the config at `path`. A missing file is an empty config.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(s) => toml::from_str(&s).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(_) if !path.exists() => Ok(Self::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn external_decoders(&self) -> Result<Vec<ExternalDecoder>, String> {
+        self.decoders
+            .iter()
+            .map(|(name, config)| match config {
+                DecoderConfig::Command(command) => Ok(ExternalDecoder {
+                    name: name.clone(),
+                    command: command.clone(),
+                    extensions: vec![name.to_lowercase()],
+                    magic: None,
+                    timeout: DEFAULT_TIMEOUT,
+                }),
+                DecoderConfig::Table {
+                    command,
+                    extensions,
+                    magic,
+                    offset,
+                    timeout,
+                } => {
+                    if magic
+                        .as_ref()
+                        .is_some_and(|magic| offset + magic.len() > decoder::HEADER_LEN as usize)
+                    {
+                        return Err(format!(
+                            "decoders.{}: magic must end within the first {} bytes",
+                            name,
+                            decoder::HEADER_LEN
+                        ));
+                    }
+                    let timeout = match timeout {
+                        Some(secs) => Duration::try_from_secs_f64(*secs)
+                            .map_err(|_| format!("decoders.{}: invalid timeout", name))?,
+                        None => DEFAULT_TIMEOUT,
+                    };
+
+                    Ok(ExternalDecoder {
+                        name: name.clone(),
+                        command: command.clone(),
+                        extensions: if extensions.is_empty() {
+                            vec![name.to_lowercase()]
+                        } else {
+                            extensions
+                                .iter()
+                                .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                                .collect()
+                        },
+                        magic: magic
+                            .as_ref()
+                            .map(|magic| (*offset, magic.as_bytes().to_vec())),
+                        timeout,
+                    })
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(toml: &str) -> Config {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn rejects_magic_beyond_the_header() {
+        let config = parse(
+            r#"
+            [decoders.pdf]
+            command = "pdftoppm {in}"
+            magic = "%PDF"
+            offset = 61
+            "#,
+        );
+        let error = config.external_decoders().unwrap_err();
+        assert_eq!(
+            error,
+            "decoders.pdf: magic must end within the first 64 bytes"
+        );
+
+        let config = parse(
+            r#"
+            [decoders.pdf]
+            command = "pdftoppm {in}"
+            magic = "%PDF"
+            offset = 60
+            "#,
+        );
+        let decoders = config.external_decoders().unwrap();
+        assert_eq!(decoders[0].magic, Some((60, b"%PDF".to_vec())));
+    }
+
+    #[test]
+    fn normalizes_extensions() {
+        let config = parse(
+            r#"
+            [decoders]
+            HEIC = "heif-convert {in} -"
+
+            [decoders.camera]
+            command = "dcraw -c {in}"
+            extensions = [".CR3", "Nef", "arw"]
+            timeout = 2.5
+            "#,
+        );
+        let decoders = config.external_decoders().unwrap();
+        assert_eq!(decoders[0].name, "HEIC");
+        assert_eq!(decoders[0].extensions, ["heic"]);
+        assert_eq!(decoders[0].timeout, DEFAULT_TIMEOUT);
+        assert_eq!(decoders[1].extensions, ["cr3", "nef", "arw"]);
+        assert_eq!(decoders[1].timeout, Duration::from_millis(2500));
+    }
+}
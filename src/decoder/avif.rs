@@ -0,0 +1,126 @@
+use eyre::Result;
+use image::{DynamicImage, RgbImage, RgbaImage};
+use re_rav1d::dav1d::{
+    pixel::{MatrixCoefficients, YUVRange},
+    Decoder, Error, Picture, PixelLayout, PlanarImageComponent, Settings,
+};
+
+/// Decodes the primary item of an AVIF, with its alpha channel if any.
+pub fn decode(data: &[u8]) -> Result<DynamicImage> {
+    let avif = avif_parse::read_avif(&mut &data[..])?;
+    let color = decode_av1(avif.primary_item.to_vec())?;
+    let (width, height) = (color.width(), color.height());
+
+    let alpha = match &avif.alpha_item {
+        Some(alpha) => Some(decode_av1(alpha.to_vec())?),
+        None => None,
+    };
+    let alpha = match alpha {
+        Some(alpha) if (alpha.width(), alpha.height()) == (width, height) => alpha,
+        _ => {
+            return Ok(DynamicImage::ImageRgb8(RgbImage::from_fn(
+                width,
+                height,
+                |x, y| image::Rgb(to_rgb(&color, x, y)),
+            )))
+        }
+    };
+
+    Ok(DynamicImage::ImageRgba8(RgbaImage::from_fn(
+        width,
+        height,
+        |x, y| {
+            let a = normalize(&alpha, PlanarImageComponent::Y, x, y).clamp(0.0, 1.0);
+            let [mut r, mut g, mut b] = to_rgb(&color, x, y);
+            if avif.premultiplied_alpha && a > 0.0 {
+                let unpremultiply = |c: u8| (c as f32 / a).min(255.0) as u8;
+                r = unpremultiply(r);
+                g = unpremultiply(g);
+                b = unpremultiply(b);
+            }
+            image::Rgba([r, g, b, (a * 255.0).round() as u8])
+        },
+    )))
+}
+
+fn decode_av1(data: Vec<u8>) -> Result<Picture> {
+    // Still images have a single frame, waiting for more is pointless.
+    let mut settings = Settings::new();
+    settings.set_max_frame_delay(1);
+    let mut decoder = Decoder::with_settings(&settings)?;
+
+    let mut sent = decoder.send_data(data, None, None, None);
+    loop {
+        match sent {
+            Ok(()) => break,
+            Err(Error::Again) => {
+                if let Ok(picture) = decoder.get_picture() {
+                    return Ok(picture);
+                }
+                sent = decoder.send_pending_data();
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(decoder.get_picture()?)
+}
+
+/// Returns a sample of the picture, scaled to `0.0..=1.0` for luma and alpha or
+/// to `-0.5..=0.5` for chroma.
+fn normalize(picture: &Picture, component: PlanarImageComponent, x: u32, y: u32) -> f32 {
+    let (x, y) = match (component, picture.pixel_layout()) {
+        (PlanarImageComponent::Y, _) | (_, PixelLayout::I444) => (x, y),
+        (_, PixelLayout::I422) => (x / 2, y),
+        _ => (x / 2, y / 2),
+    };
+
+    let plane = picture.plane(component);
+    let stride = picture.stride(component) as usize;
+    let depth = picture.bit_depth();
+    let sample = if depth > 8 {
+        let i = y as usize * stride + x as usize * 2;
+        u16::from_le_bytes([plane[i], plane[i + 1]]) as f32
+    } else {
+        plane[y as usize * stride + x as usize] as f32
+    };
+
+    let scale = (1 << (depth - 8)) as f32;
+    let chroma = component != PlanarImageComponent::Y;
+    match (picture.color_range(), chroma) {
+        (YUVRange::Limited, false) => (sample / scale - 16.0) / 219.0,
+        (YUVRange::Limited, true) => (sample / scale - 128.0) / 224.0,
+        (YUVRange::Full, false) => sample / ((1 << depth) - 1) as f32,
+        (YUVRange::Full, true) => (sample - (1 << (depth - 1)) as f32) / ((1 << depth) - 1) as f32,
+    }
+}
+
+fn to_rgb(picture: &Picture, x: u32, y: u32) -> [u8; 3] {
+    let luma = normalize(picture, PlanarImageComponent::Y, x, y);
+    if picture.pixel_layout() == PixelLayout::I400 {
+        let v = to_u8(luma);
+        return [v, v, v];
+    }
+
+    let cb = normalize(picture, PlanarImageComponent::U, x, y);
+    let cr = normalize(picture, PlanarImageComponent::V, x, y);
+
+    // Identity stores G, B and R in the Y, U and V planes.
+    let (kr, kb) = match picture.matrix_coefficients() {
+        MatrixCoefficients::Identity => return [to_u8(cr + 0.5), to_u8(luma), to_u8(cb + 0.5)],
+        MatrixCoefficients::BT709 => (0.2126, 0.0722),
+        MatrixCoefficients::BT2020NonConstantLuminance
+        | MatrixCoefficients::BT2020ConstantLuminance => (0.2627, 0.0593),
+        MatrixCoefficients::ST240M => (0.212, 0.087),
+        _ => (0.299, 0.114),
+    };
+
+    let r = luma + 2.0 * (1.0 - kr) * cr;
+    let b = luma + 2.0 * (1.0 - kb) * cb;
+    let g = (luma - kr * r - kb * b) / (1.0 - kr - kb);
+    [to_u8(r), to_u8(g), to_u8(b)]
+}
+
+fn to_u8(v: f32) -> u8 {
+    (v.clamp(0.0, 1.0) * 255.0).round() as u8
+}
@@ -0,0 +1,21 @@
+use crate::transform::Transform;
+use exif::{In, Reader, Tag};
+use std::{fs::File, io::BufReader, path::Path};
+
+/// Reads the EXIF Orientation tag, ignoring files without a valid one.
+pub fn orientation(path: &Path) -> Transform {
+    let orientation = File::open(path)
+        .ok()
+        .and_then(|file| {
+            Reader::new()
+                .read_from_container(&mut BufReader::new(file))
+                .ok()
+        })
+        .and_then(|exif| {
+            exif.get_field(Tag::Orientation, In::PRIMARY)?
+                .value
+                .get_uint(0)
+        });
+
+    orientation.map_or_else(Transform::default, Transform::from_exif)
+}
@@ -0,0 +1,244 @@
+use eyre::Result;
+use image::DynamicImage;
+use std::{
+    io::Read,
+    path::Path,
+    process::Stdio,
+    sync::OnceLock,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How often a running decoder is checked for completion.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+static DECODERS: OnceLock<Vec<ExternalDecoder>> = OnceLock::new();
+
+/// A command converting files to an image picterm can decode.
+#[derive(Debug, Clone)]
+pub struct ExternalDecoder {
+    pub name: String,
+    /// Run by the shell, with `{in}` replaced by the path of the file.
+    pub command: String,
+    /// In lowercase.
+    pub extensions: Vec<String>,
+    /// Bytes at an offset recognizing files regardless of their extension.
+    pub magic: Option<(usize, Vec<u8>)>,
+    pub timeout: Duration,
+}
+
+impl ExternalDecoder {
+    fn matches(&self, ext: Option<&str>, header: &[u8]) -> bool {
+        let by_magic = self.magic.as_ref().is_some_and(|(offset, magic)| {
+            header.get(*offset..offset + magic.len()) == Some(magic.as_slice())
+        });
+        let by_extension = ext.is_some_and(|ext| self.extensions.contains(&ext.to_lowercase()));
+        by_magic || by_extension
+    }
+}
+
+/// Sets the decoders tried before the built-in ones. Only the first call has
+/// an effect.
+pub fn register(decoders: Vec<ExternalDecoder>) {
+    let _ = DECODERS.set(decoders);
+}
+
+pub fn decoders() -> &'static [ExternalDecoder] {
+    DECODERS.get().map_or(&[], Vec::as_slice)
+}
+
+/// Index of the first decoder matching the extension or the header of a file.
+pub fn find(ext: Option<&str>, header: &[u8]) -> Option<usize> {
+    decoders()
+        .iter()
+        .position(|decoder| decoder.matches(ext, header))
+}
+
+/// Runs the decoder on `path` and decodes what it writes to its stdout.
+pub fn decode(decoder: &ExternalDecoder, path: &Path) -> Result<DynamicImage> {
+    let command = decoder
+        .command
+        .replace("{in}", &sys::quote(&path.to_string_lossy()));
+    let mut child = sys::shell(&command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| eyre::eyre!("Cannot run the {} decoder: {}", decoder.name, e))?;
+
+    // Pipes are drained while waiting, a full one would block the command.
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() > decoder.timeout {
+            sys::kill(&mut child);
+            eyre::bail!(
+                "The {} decoder timed out after {:?}",
+                decoder.name,
+                decoder.timeout
+            );
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr.join().unwrap_or_default()).into_owned();
+        match stderr.lines().rfind(|line| !line.trim().is_empty()) {
+            Some(line) => eyre::bail!("The {} decoder failed ({}): {}", decoder.name, status, line),
+            None => eyre::bail!("The {} decoder failed ({})", decoder.name, status),
+        }
+    }
+
+    let output = stdout.join().unwrap_or_default();
+    image::load_from_memory(&output)
+        .map_err(|e| eyre::eyre!("The {} decoder wrote no image: {}", decoder.name, e))
+}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = vec![];
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::{
+        os::unix::process::CommandExt,
+        process::{Child, Command},
+    };
+
+    /// Runs `command` in its own process group, so that the commands it
+    /// starts can be killed with it.
+    pub fn shell(command: &str) -> Command {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command).process_group(0);
+        shell
+    }
+
+    pub fn kill(child: &mut Child) {
+        unsafe {
+            libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+        }
+        let _ = child.wait();
+    }
+
+    /// Quotes `s` as a single shell word.
+    pub fn quote(s: &str) -> String {
+        format!("'{}'", s.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(not(unix))]
+mod sys {
+    use std::process::{Child, Command};
+
+    pub fn shell(command: &str) -> Command {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    }
+
+    pub fn kill(child: &mut Child) {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+
+    pub fn quote(s: &str) -> String {
+        format!("\"{}\"", s)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use image::GenericImageView;
+    use std::{env, fs, path::PathBuf, process};
+
+    /// Writes a shell script run as the decoder and returns a decoder running
+    /// it on the file.
+    fn script_decoder(name: &str, script: &str, timeout: Duration) -> (ExternalDecoder, PathBuf) {
+        let dir = env::temp_dir().join(format!("picterm-external-{}-{}", process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("decoder.sh");
+        fs::write(&path, script).unwrap();
+
+        let decoder = ExternalDecoder {
+            name: name.to_string(),
+            command: format!("sh {} {{in}}", sys::quote(&path.to_string_lossy())),
+            extensions: vec![],
+            magic: None,
+            timeout,
+        };
+        (decoder, dir)
+    }
+
+    /// Whether `pid` is a live process, zombies aside.
+    fn is_running(pid: &str) -> bool {
+        fs::read_to_string(format!("/proc/{}/stat", pid)).is_ok_and(|stat| !stat.contains(") Z "))
+    }
+
+    #[test]
+    fn decodes_stdout() {
+        let (decoder, dir) = script_decoder(
+            "ppm",
+            "printf 'P3\\n2 1\\n255\\n255 0 0 0 0 255\\n'\n",
+            Duration::from_secs(10),
+        );
+        let img = decode(&decoder, &dir.join("in file.xyz")).unwrap();
+        assert_eq!(img.dimensions(), (2, 1));
+        assert_eq!(img.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(img.get_pixel(1, 0).0, [0, 0, 255, 255]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reports_the_last_stderr_line() {
+        let (decoder, dir) = script_decoder(
+            "failing",
+            "echo 'starting' >&2\necho \"cannot read $1\" >&2\necho >&2\nexit 3\n",
+            Duration::from_secs(10),
+        );
+        let path = dir.join("broken.xyz");
+        let error = decode(&decoder, &path).unwrap_err().to_string();
+        assert!(error.starts_with("The failing decoder failed"), "{}", error);
+        assert!(error.contains("exit status: 3"), "{}", error);
+        assert!(
+            error.ends_with(&format!("cannot read {}", path.display())),
+            "{}",
+            error
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn kills_the_process_group_on_timeout() {
+        let (decoder, dir) = script_decoder(
+            "slow",
+            "sleep 30 &\necho $! > \"$1\"\nwait\n",
+            Duration::from_millis(200),
+        );
+        let pid_file = dir.join("pid");
+        let started = Instant::now();
+        let error = decode(&decoder, &pid_file).unwrap_err().to_string();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(error.contains("slow decoder timed out"), "{}", error);
+
+        let pid = fs::read_to_string(&pid_file).unwrap();
+        let pid = pid.trim();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while is_running(pid) && Instant::now() < deadline {
+            thread::sleep(POLL_INTERVAL);
+        }
+        assert!(!is_running(pid), "sleep {} is still running", pid);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
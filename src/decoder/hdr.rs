@@ -0,0 +1,32 @@
+use super::HdrImage;
+use eyre::Result;
+use image::{codecs::hdr::HdrDecoder, Rgba};
+use std::{fs::File, io::BufReader, path::Path};
+
+/// Decodes a Radiance HDR to linear values.
+pub fn radiance(path: &Path) -> Result<HdrImage> {
+    let decoder = HdrDecoder::new(BufReader::new(File::open(path)?))?;
+    let metadata = decoder.metadata();
+    let pixels = decoder.read_image_hdr()?;
+
+    let buf = pixels
+        .iter()
+        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 1.0])
+        .collect();
+    HdrImage::from_raw(metadata.width, metadata.height, buf)
+        .ok_or_else(|| eyre::eyre!("Truncated Radiance HDR image"))
+}
+
+/// Decodes the first RGB layer of an OpenEXR, at its largest resolution.
+/// Layers without alpha are opaque.
+pub fn openexr(path: &Path) -> Result<HdrImage> {
+    let image = exr::prelude::read_first_rgba_layer_from_file(
+        path,
+        |resolution, _| HdrImage::new(resolution.width() as u32, resolution.height() as u32),
+        |img: &mut HdrImage, position, (r, g, b, a): (f32, f32, f32, f32)| {
+            img.put_pixel(position.x() as u32, position.y() as u32, Rgba([r, g, b, a]));
+        },
+    )?;
+
+    Ok(image.layer_data.channel_data.pixels)
+}
@@ -0,0 +1,44 @@
+use super::{still, Frame};
+use eyre::Result;
+use image::{GenericImageView, ImageFormat};
+
+const HEADER_LEN: usize = 6;
+const ENTRY_LEN: usize = 16;
+
+/// Decodes every entry of an icon, largest first. The `image` crate only
+/// decodes the best entry, so each one is repackaged as a single-entry icon.
+pub fn entries(data: &[u8]) -> Result<Vec<Frame>> {
+    if data.len() < HEADER_LEN {
+        eyre::bail!("Truncated ICO header");
+    }
+    let count = u16::from_le_bytes([data[4], data[5]]) as usize;
+
+    let mut entries = (0..count)
+        .filter_map(|i| {
+            let entry = data.get(HEADER_LEN + i * ENTRY_LEN..HEADER_LEN + (i + 1) * ENTRY_LEN)?;
+            let size = u32::from_le_bytes([entry[8], entry[9], entry[10], entry[11]]) as usize;
+            let offset = u32::from_le_bytes([entry[12], entry[13], entry[14], entry[15]]) as usize;
+            let image = data.get(offset..offset.checked_add(size)?)?;
+
+            let mut icon = vec![0, 0, 1, 0, 1, 0];
+            icon.extend_from_slice(&entry[..12]);
+            icon.extend_from_slice(&((HEADER_LEN + ENTRY_LEN) as u32).to_le_bytes());
+            icon.extend_from_slice(image);
+            image::load_from_memory_with_format(&icon, ImageFormat::Ico).ok()
+        })
+        .collect::<Vec<_>>();
+
+    if entries.is_empty() {
+        return Ok(vec![still(image::load_from_memory_with_format(
+            data,
+            ImageFormat::Ico,
+        )?)]);
+    }
+
+    entries.sort_by_key(|img| {
+        let (width, height) = img.dimensions();
+        std::cmp::Reverse(width as u64 * height as u64)
+    });
+
+    Ok(entries.into_iter().map(still).collect())
+}
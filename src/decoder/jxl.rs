@@ -0,0 +1,56 @@
+use super::{frame_delay, frame_limit, Frame, Pixels};
+use eyre::Result;
+use image::{DynamicImage, ImageBuffer};
+use jxl_oxide::{JxlImage, PixelFormat};
+use std::{path::Path, time::Duration};
+
+/// Decodes every keyframe of a JPEG XL, which are the frames of an animation.
+pub fn frames(path: &Path) -> Result<Vec<Frame>> {
+    let image = JxlImage::builder()
+        .open(path)
+        .map_err(|e| eyre::eyre!("{}", e))?;
+    if matches!(image.pixel_format(), PixelFormat::Cmyk | PixelFormat::Cmyka) {
+        eyre::bail!("CMYK JPEG XL images are not supported");
+    }
+
+    // Durations are counted in ticks of `tps_denominator / tps_numerator` seconds.
+    let ticks = image
+        .image_header()
+        .metadata
+        .animation
+        .as_ref()
+        .map(|animation| (animation.tps_numerator, animation.tps_denominator));
+
+    let limit = frame_limit((image.width(), image.height()), 4);
+    (0..image.num_loaded_keyframes().min(limit))
+        .map(|index| {
+            let render = image
+                .render_frame(index)
+                .map_err(|e| eyre::eyre!("{}", e))?;
+            let mut stream = render.stream();
+            let (width, height) = (stream.width(), stream.height());
+            let mut buf = vec![0u8; (width * height * stream.channels()) as usize];
+            stream.write_to_buffer(&mut buf);
+
+            let img = match stream.channels() {
+                1 => ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLuma8),
+                2 => ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLumaA8),
+                3 => ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgb8),
+                _ => ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgba8),
+            }
+            .ok_or_else(|| eyre::eyre!("Truncated JPEG XL frame"))?;
+
+            let delay = match ticks {
+                Some((numerator, denominator)) if numerator > 0 => frame_delay(
+                    (render.duration() as u64 * denominator as u64 * 1000 / numerator as u64)
+                        as u32,
+                ),
+                _ => Duration::ZERO,
+            };
+            Ok(Frame {
+                pixels: Pixels::Ldr(img),
+                delay,
+            })
+        })
+        .collect()
+}
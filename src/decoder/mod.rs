@@ -0,0 +1,606 @@
+#[cfg(feature = "avif")]
+mod avif;
+mod exif;
+pub mod external;
+mod hdr;
+mod ico;
+#[cfg(feature = "jxl")]
+mod jxl;
+mod ora;
+mod psd;
+#[cfg(feature = "raw")]
+mod raw;
+mod svg;
+mod tiff;
+mod webp;
+
+use crate::transform::Transform;
+use eyre::Result;
+use image::{
+    codecs::{gif::GifDecoder, png::PngDecoder},
+    AnimationDecoder, DynamicImage, GenericImageView, ImageBuffer, ImageDecoder, ImageFormat, Rgba,
+};
+use std::{
+    fmt::{self, Display},
+    fs::{self, File},
+    io::{BufReader, Read},
+    path::Path,
+    time::Duration,
+};
+
+/// Delay used for frames that do not specify a usable one, as browsers do.
+const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+
+/// Shorter delays are treated as unspecified.
+const MIN_DELAY: Duration = Duration::from_millis(20);
+
+/// Memory the decoded frames of an animation may take. Later frames are
+/// dropped, playing a shorter animation rather than exhausting the memory.
+const FRAMES_BUDGET: usize = 512 * 1024 * 1024;
+
+/// Bytes read from the start of a file to recognize its format, enough for the
+/// mime type at the start of zip based documents.
+pub(crate) const HEADER_LEN: u64 = 64;
+
+/// Image formats that can be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Png,
+    Jpeg,
+    Gif,
+    WebP,
+    Bmp,
+    Ico,
+    Tiff,
+    Tga,
+    Pnm,
+    Dds,
+    Hdr,
+    Exr,
+    Farbfeld,
+    Qoi,
+    Avif,
+    Jxl,
+    Svg,
+    Raw,
+    Psd,
+    Ora,
+    Kra,
+    /// The external decoder at this index.
+    External(usize),
+}
+
+impl Format {
+    const ALL: [Format; 21] = [
+        Format::Png,
+        Format::Jpeg,
+        Format::Gif,
+        Format::WebP,
+        Format::Bmp,
+        Format::Ico,
+        Format::Tiff,
+        Format::Tga,
+        Format::Pnm,
+        Format::Dds,
+        Format::Hdr,
+        Format::Exr,
+        Format::Farbfeld,
+        Format::Qoi,
+        Format::Avif,
+        Format::Jxl,
+        Format::Svg,
+        Format::Raw,
+        Format::Psd,
+        Format::Ora,
+        Format::Kra,
+    ];
+
+    /// Extensions of the format, in lowercase.
+    pub fn extensions(self) -> Vec<&'static str> {
+        let extensions: &[&str] = match self {
+            Format::Png => &["png", "apng"],
+            Format::Jpeg => &["jpg", "jpeg"],
+            Format::Gif => &["gif"],
+            Format::WebP => &["webp"],
+            Format::Bmp => &["bmp"],
+            Format::Ico => &["ico"],
+            Format::Tiff => &["tif", "tiff"],
+            Format::Tga => &["tga"],
+            Format::Pnm => &["pbm", "pgm", "ppm", "pam", "pnm"],
+            Format::Dds => &["dds"],
+            Format::Hdr => &["hdr"],
+            Format::Exr => &["exr"],
+            Format::Farbfeld => &["ff", "farbfeld"],
+            Format::Qoi => &["qoi"],
+            Format::Avif => &["avif"],
+            Format::Jxl => &["jxl"],
+            Format::Svg => &["svg", "svgz"],
+            Format::Raw => &[
+                "cr2", "cr3", "nef", "nrw", "arw", "dng", "raf", "orf", "rw2", "pef",
+            ],
+            Format::Psd => &["psd"],
+            Format::Ora => &["ora"],
+            Format::Kra => &["kra"],
+            Format::External(i) => {
+                return external::decoders()[i]
+                    .extensions
+                    .iter()
+                    .map(String::as_str)
+                    .collect()
+            }
+        };
+        extensions.to_vec()
+    }
+
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        if let Some(i) = external::find(Some(ext), &[]) {
+            return Some(Format::External(i));
+        }
+
+        let ext = ext.to_lowercase();
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|format| format.extensions().contains(&ext.as_str()))
+    }
+
+    fn from_signature(header: &[u8]) -> Option<Self> {
+        if header.starts_with(b"qoif") {
+            return Some(Format::Qoi);
+        }
+        if header.starts_with(&[0x76, 0x2f, 0x31, 0x01]) {
+            return Some(Format::Exr);
+        }
+        if header.starts_with(b"8BPS") {
+            return Some(Format::Psd);
+        }
+        // Zip archives that store their mime type first, uncompressed.
+        if header.starts_with(b"PK\x03\x04") && header.get(30..38) == Some(b"mimetype") {
+            let mime = &header[38..];
+            if mime.starts_with(b"image/openraster") {
+                return Some(Format::Ora);
+            }
+            if mime.starts_with(b"application/x-krita") {
+                return Some(Format::Kra);
+            }
+        }
+        if markup(header).starts_with(b"<svg") {
+            return Some(Format::Svg);
+        }
+        // The `ftyp` box of an image or an image sequence.
+        if header.get(4..12) == Some(b"ftypavif") || header.get(4..12) == Some(b"ftypavis") {
+            return Some(Format::Avif);
+        }
+        // A bare codestream or the ISOBMFF container.
+        if header.starts_with(&[0xff, 0x0a]) || header.starts_with(b"\0\0\0\x0cJXL \r\n\x87\n") {
+            return Some(Format::Jxl);
+        }
+
+        let format = match image::guess_format(header).ok()? {
+            ImageFormat::Png => Format::Png,
+            ImageFormat::Jpeg => Format::Jpeg,
+            ImageFormat::Gif => Format::Gif,
+            ImageFormat::WebP => Format::WebP,
+            ImageFormat::Bmp => Format::Bmp,
+            ImageFormat::Ico => Format::Ico,
+            ImageFormat::Tiff => Format::Tiff,
+            ImageFormat::Pnm => Format::Pnm,
+            ImageFormat::Dds => Format::Dds,
+            ImageFormat::Hdr => Format::Hdr,
+            ImageFormat::Farbfeld => Format::Farbfeld,
+            _ => return None,
+        };
+        Some(format)
+    }
+
+    /// Whether support for the format was enabled with its cargo feature.
+    // The arms are constants that depend on the enabled features.
+    #[allow(clippy::match_like_matches_macro)]
+    fn is_enabled(self) -> bool {
+        match self {
+            Format::Avif => cfg!(feature = "avif"),
+            Format::Jxl => cfg!(feature = "jxl"),
+            Format::Raw => cfg!(feature = "raw"),
+            _ => true,
+        }
+    }
+
+    /// Whether a file with an extension of the format holds it, for the formats
+    /// without a reliable signature.
+    fn matches_extension(self, header: &[u8]) -> bool {
+        match self {
+            Format::Tga => true,
+            // An XML prolog or comment, or gzip for svgz.
+            Format::Svg => markup(header).starts_with(b"<") || header.starts_with(&[0x1f, 0x8b]),
+            // TIFF based formats, including ORF and RW2 with their own magic
+            // numbers, RAF and CR3.
+            Format::Raw => {
+                header.starts_with(b"II")
+                    || header.starts_with(b"MM")
+                    || header.starts_with(b"FUJIFILMCCD-RAW")
+                    || header.get(4..12) == Some(b"ftypcrx ")
+            }
+            Format::Ora | Format::Kra => header.starts_with(b"PK\x03\x04"),
+            _ => false,
+        }
+    }
+
+    /// The format decoded with `image::load`, for the formats without a dedicated decoder.
+    fn image_format(self) -> Option<ImageFormat> {
+        match self {
+            Format::Jpeg => Some(ImageFormat::Jpeg),
+            Format::Bmp => Some(ImageFormat::Bmp),
+            Format::Tga => Some(ImageFormat::Tga),
+            Format::Pnm => Some(ImageFormat::Pnm),
+            Format::Dds => Some(ImageFormat::Dds),
+            Format::Farbfeld => Some(ImageFormat::Farbfeld),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            Format::Png => "PNG",
+            Format::Jpeg => "JPEG",
+            Format::Gif => "GIF",
+            Format::WebP => "WebP",
+            Format::Bmp => "BMP",
+            Format::Ico => "ICO",
+            Format::Tiff => "TIFF",
+            Format::Tga => "TGA",
+            Format::Pnm => "PNM",
+            Format::Dds => "DDS",
+            Format::Hdr => "Radiance HDR",
+            Format::Exr => "OpenEXR",
+            Format::Farbfeld => "farbfeld",
+            Format::Qoi => "QOI",
+            Format::Avif => "AVIF",
+            Format::Jxl => "JPEG XL",
+            Format::Svg => "SVG",
+            Format::Raw => "Camera RAW",
+            Format::Psd => "PSD",
+            Format::Ora => "OpenRaster",
+            Format::Kra => "Krita",
+            Format::External(i) => {
+                return write!(f, "{} (external)", external::decoders()[*i].name);
+            }
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Detects the format of the image at `path` from its header, falling back to
+/// the extension for formats without a signature. Returns `None` for files that
+/// cannot be decoded, including formats whose cargo feature is disabled.
+pub fn detect_format(path: &Path) -> Option<Format> {
+    let mut header = vec![];
+    File::open(path)
+        .ok()?
+        .take(HEADER_LEN)
+        .read_to_end(&mut header)
+        .ok()?;
+
+    detect(path.extension().and_then(|ext| ext.to_str()), &header)
+}
+
+/// Recognizes the format of a file from its extension and its first
+/// `HEADER_LEN` bytes.
+fn detect(ext: Option<&str>, header: &[u8]) -> Option<Format> {
+    // External decoders come first, they may replace a built-in one.
+    if let Some(i) = external::find(ext, header) {
+        return Some(Format::External(i));
+    }
+
+    let by_extension = ext
+        .and_then(Format::from_extension)
+        .filter(|format| format.matches_extension(header));
+    // Most camera RAW files are TIFFs that only their extension tells apart.
+    let format = match by_extension {
+        Some(Format::Raw) => by_extension,
+        _ => Format::from_signature(header).or(by_extension),
+    };
+
+    format.filter(|format| format.is_enabled())
+}
+
+/// Skips the byte order mark and the whitespace before the first tag of a markup file.
+fn markup(header: &[u8]) -> &[u8] {
+    let header = header.strip_prefix(b"\xef\xbb\xbf").unwrap_or(header);
+    let start = header
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(header.len());
+    &header[start..]
+}
+
+/// Linear values of a high dynamic range image.
+pub type HdrImage = ImageBuffer<Rgba<f32>, Vec<f32>>;
+
+/// Decoded pixels. High dynamic range images keep their linear values until
+/// they are tone mapped for display.
+#[derive(Debug, Clone)]
+pub enum Pixels {
+    Ldr(DynamicImage),
+    Hdr(HdrImage),
+}
+
+impl Pixels {
+    pub fn dimensions(&self) -> (u32, u32) {
+        match self {
+            Pixels::Ldr(img) => img.dimensions(),
+            Pixels::Hdr(img) => img.dimensions(),
+        }
+    }
+}
+
+/// A decoded image, or a single frame of an animation.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub pixels: Pixels,
+    /// How long the frame is shown, zero for still images and pages.
+    pub delay: Duration,
+}
+
+/// The frames of an animation, the pages of a document or the entries of an
+/// icon. Still images have a single frame.
+#[derive(Debug, Clone)]
+pub struct DecodedImage {
+    pub format: Format,
+    /// Size of the image, which differs from the frames for vector images.
+    pub dimensions: (u32, u32),
+    pub frames: Vec<Frame>,
+    /// Names of the layers of a layered document, top first.
+    pub layers: Vec<String>,
+    /// How the frames are displayed upright, from the EXIF orientation.
+    pub orientation: Transform,
+}
+
+/// Decodes every frame of the image at `path`. Vector images are rasterized to
+/// fit `resolution`.
+pub fn open(path: &Path, resolution: (u32, u32)) -> Result<DecodedImage> {
+    let format = detect_format(path)
+        .ok_or_else(|| eyre::eyre!("{} is not a supported image", path.display()))?;
+
+    let mut dimensions = None;
+    let mut layers = vec![];
+    let frames = match format {
+        Format::Gif => {
+            let decoder = GifDecoder::new(BufReader::new(File::open(path)?))?;
+            let limit = frame_limit(decoder.dimensions(), 4);
+            collect_frames(decoder, limit)?
+        }
+        Format::Png => {
+            let decoder = PngDecoder::new(BufReader::new(File::open(path)?))?;
+            if decoder.is_apng() {
+                let limit = frame_limit(decoder.dimensions(), 4);
+                collect_frames(decoder.apng(), limit)?
+            } else {
+                vec![still(DynamicImage::from_decoder(decoder)?)]
+            }
+        }
+        Format::WebP => webp::frames(path)?,
+        Format::Ico => ico::entries(&fs::read(path)?)?,
+        Format::Tiff => self::tiff::pages(path)?,
+        Format::Hdr => vec![still_hdr(hdr::radiance(path)?)],
+        Format::Exr => vec![still_hdr(hdr::openexr(path)?)],
+        Format::Svg => {
+            let (img, size) = svg::rasterize(path, &fs::read(path)?, resolution)?;
+            dimensions = Some(size);
+            vec![still(img)]
+        }
+        Format::Psd => {
+            let (img, names) = self::psd::decode(&fs::read(path)?)?;
+            layers = names;
+            vec![still(img)]
+        }
+        Format::Ora => {
+            let (img, names) = ora::decode(path, "stack.xml", "Thumbnails/thumbnail.png")?;
+            layers = names;
+            vec![still(img)]
+        }
+        Format::Kra => {
+            let (img, names) = ora::decode(path, "maindoc.xml", "preview.png")?;
+            layers = names;
+            vec![still(img)]
+        }
+        Format::Qoi => {
+            let (header, buf) = qoi::decode_to_vec(fs::read(path)?)?;
+            let img = match header.channels {
+                qoi::Channels::Rgb => ImageBuffer::from_raw(header.width, header.height, buf)
+                    .map(DynamicImage::ImageRgb8),
+                qoi::Channels::Rgba => ImageBuffer::from_raw(header.width, header.height, buf)
+                    .map(DynamicImage::ImageRgba8),
+            };
+            vec![still(
+                img.ok_or_else(|| eyre::eyre!("Truncated QOI image"))?,
+            )]
+        }
+        #[cfg(feature = "avif")]
+        Format::Avif => vec![still(avif::decode(&fs::read(path)?)?)],
+        #[cfg(feature = "jxl")]
+        Format::Jxl => jxl::frames(path)?,
+        #[cfg(feature = "raw")]
+        Format::Raw => vec![still(raw::decode(path)?)],
+        Format::External(i) => vec![still(external::decode(&external::decoders()[i], path)?)],
+        format => {
+            let image_format = format
+                .image_format()
+                .ok_or_else(|| eyre::eyre!("{} support is not enabled", format))?;
+            vec![still(image::load(
+                BufReader::new(File::open(path)?),
+                image_format,
+            )?)]
+        }
+    };
+
+    if frames.is_empty() {
+        eyre::bail!("{} has no frames", path.display());
+    }
+
+    // Other decoders apply the orientation themselves or have none. Most camera
+    // RAW files are TIFF containers, whose sensor data and previews are stored
+    // unrotated.
+    let orientation = match format {
+        Format::Jpeg | Format::Png | Format::WebP | Format::Tiff | Format::Raw => {
+            self::exif::orientation(path)
+        }
+        _ => Transform::default(),
+    };
+
+    Ok(DecodedImage {
+        format,
+        dimensions: dimensions.unwrap_or_else(|| frames[0].pixels.dimensions()),
+        frames,
+        layers,
+        orientation,
+    })
+}
+
+/// Decodes the preview a camera embeds in a RAW file, which shows much sooner
+/// than the developed sensor data. Other images have no preview.
+#[cfg(feature = "raw")]
+pub fn open_preview(path: &Path) -> Option<DecodedImage> {
+    if detect_format(path) != Some(Format::Raw) {
+        return None;
+    }
+    let img = raw::preview(path).ok()??;
+
+    Some(DecodedImage {
+        format: Format::Raw,
+        dimensions: img.dimensions(),
+        frames: vec![still(img)],
+        layers: vec![],
+        orientation: self::exif::orientation(path),
+    })
+}
+
+#[cfg(not(feature = "raw"))]
+pub fn open_preview(_path: &Path) -> Option<DecodedImage> {
+    None
+}
+
+fn still(img: DynamicImage) -> Frame {
+    Frame {
+        pixels: Pixels::Ldr(img),
+        delay: Duration::ZERO,
+    }
+}
+
+fn still_hdr(img: HdrImage) -> Frame {
+    Frame {
+        pixels: Pixels::Hdr(img),
+        delay: Duration::ZERO,
+    }
+}
+
+/// Composites the frames of a GIF or an APNG, applying their disposal and blending.
+fn collect_frames<'a>(decoder: impl AnimationDecoder<'a>, limit: usize) -> Result<Vec<Frame>> {
+    decoder
+        .into_frames()
+        .take(limit)
+        .map(|frame| {
+            let frame = frame?;
+            let (numer, denom) = frame.delay().numer_denom_ms();
+            Ok(Frame {
+                delay: frame_delay(numer / denom.max(1)),
+                pixels: Pixels::Ldr(DynamicImage::ImageRgba8(frame.into_buffer())),
+            })
+        })
+        .collect()
+}
+
+/// Number of frames of `dimensions` with `channels` bytes per pixel that fit
+/// in the memory budget of an animation, at least one.
+fn frame_limit((width, height): (u32, u32), channels: usize) -> usize {
+    let frame = width as usize * height as usize * channels;
+    (FRAMES_BUDGET / frame.max(1)).max(1)
+}
+
+fn frame_delay(millis: u32) -> Duration {
+    let delay = Duration::from_millis(millis as u64);
+    if delay < MIN_DELAY {
+        DEFAULT_DELAY
+    } else {
+        delay
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    const JPEG: &[u8] = b"\xff\xd8\xff\xe0\0\x10JFIF\0";
+    /// The header of an uncompressed true color TGA, which has no signature.
+    const TGA: &[u8] = b"\0\0\x02\0\0\0\0\0\0\0\0\0\x10\0\x10\0\x20\x08";
+
+    /// A zip archive starting with an uncompressed `mimetype` entry.
+    fn zip(mime: &[u8]) -> Vec<u8> {
+        let mut header = b"PK\x03\x04".to_vec();
+        header.resize(30, 0);
+        header.extend_from_slice(b"mimetype");
+        header.extend_from_slice(mime);
+        header
+    }
+
+    #[test]
+    fn ignores_the_case_of_extensions() {
+        assert_eq!(detect(Some("JPG"), JPEG), Some(Format::Jpeg));
+        assert_eq!(detect(Some("TGA"), TGA), Some(Format::Tga));
+        assert_eq!(Format::from_extension("Png"), Some(Format::Png));
+    }
+
+    #[test]
+    fn prefers_the_signature_over_the_extension() {
+        assert_eq!(detect(None, PNG), Some(Format::Png));
+        assert_eq!(detect(Some("jpg"), PNG), Some(Format::Png));
+        assert_eq!(detect(Some("png"), JPEG), Some(Format::Jpeg));
+    }
+
+    #[test]
+    fn trusts_the_extension_without_a_signature() {
+        assert_eq!(detect(Some("tga"), TGA), Some(Format::Tga));
+        assert_eq!(detect(None, TGA), None);
+        assert!(Format::Tga.matches_extension(b""));
+        assert!(!Format::Jpeg.matches_extension(TGA));
+        assert_eq!(detect(Some("jpg"), TGA), None);
+    }
+
+    #[test]
+    fn reads_the_mime_type_of_zip_documents() {
+        let ora = zip(b"image/openraster");
+        let kra = zip(b"application/x-krita");
+        assert_eq!(Format::from_signature(&ora), Some(Format::Ora));
+        assert_eq!(Format::from_signature(&kra), Some(Format::Kra));
+        assert_eq!(detect(None, &ora), Some(Format::Ora));
+        assert_eq!(detect(Some("zip"), &kra), Some(Format::Kra));
+
+        let other = zip(b"application/epub+zip");
+        assert_eq!(detect(None, &other), None);
+        assert_eq!(detect(Some("ora"), &other), Some(Format::Ora));
+        assert_eq!(detect(Some("kra"), b"not a zip"), None);
+    }
+
+    #[test]
+    fn skips_the_byte_order_mark_and_prolog_of_svg() {
+        let bom = b"\xef\xbb\xbf\n  <svg xmlns=\"http://www.w3.org/2000/svg\">";
+        assert_eq!(Format::from_signature(bom), Some(Format::Svg));
+        assert_eq!(detect(None, bom), Some(Format::Svg));
+
+        // Only the root element is a signature, a prolog needs the extension.
+        let prolog = b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!-- drawn by hand -->";
+        assert_eq!(Format::from_signature(prolog), None);
+        assert_eq!(detect(Some("svg"), prolog), Some(Format::Svg));
+        assert_eq!(detect(Some("svgz"), b"\x1f\x8b\x08\0"), Some(Format::Svg));
+        assert_eq!(detect(Some("svg"), b"plain text"), None);
+    }
+
+    #[test]
+    fn rejects_unknown_files() {
+        assert_eq!(detect(Some("txt"), b"hello, world"), None);
+        assert_eq!(detect(None, b""), None);
+        assert_eq!(Format::from_extension("txt"), None);
+    }
+}
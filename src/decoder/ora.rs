@@ -0,0 +1,48 @@
+use eyre::Result;
+use image::{DynamicImage, ImageFormat};
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+use zip::ZipArchive;
+
+type Archive = ZipArchive<BufReader<File>>;
+
+/// Reads the flattened image of an OpenRaster or a Krita document, zip archives
+/// with a merged PNG, and the names of its layers, top first. `stack` is the
+/// XML file listing the layers and `thumbnail` the image shown by older files
+/// without a merged one.
+pub fn decode(path: &Path, stack: &str, thumbnail: &str) -> Result<(DynamicImage, Vec<String>)> {
+    let mut archive = ZipArchive::new(BufReader::new(File::open(path)?))?;
+    let png = read(&mut archive, "mergedimage.png").or_else(|_| read(&mut archive, thumbnail))?;
+    let img = image::load_from_memory_with_format(&png, ImageFormat::Png)?;
+
+    // The layers are only informative, a broken list does not prevent viewing.
+    let layers = read(&mut archive, stack)
+        .ok()
+        .and_then(|xml| layer_names(&xml))
+        .unwrap_or_default();
+    Ok((img, layers))
+}
+
+fn read(archive: &mut Archive, name: &str) -> Result<Vec<u8>> {
+    let mut file = archive.by_name(name)?;
+    let mut buf = vec![];
+    file.read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// Names of the `layer` elements, nested ones included. OpenRaster groups and
+/// masks use other elements, Krita groups have a node type.
+fn layer_names(xml: &[u8]) -> Option<Vec<String>> {
+    let doc = roxmltree::Document::parse(std::str::from_utf8(xml).ok()?).ok()?;
+    let names = doc
+        .descendants()
+        .filter(|node| node.has_tag_name("layer"))
+        .filter(|node| node.attribute("nodetype") != Some("grouplayer"))
+        .filter_map(|node| node.attribute("name"))
+        .map(str::to_string)
+        .collect();
+    Some(names)
+}
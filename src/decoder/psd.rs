@@ -0,0 +1,17 @@
+use eyre::Result;
+use image::{DynamicImage, RgbaImage};
+use psd::Psd;
+
+/// Decodes the composite image of a PSD and the names of its layers, top first.
+pub fn decode(data: &[u8]) -> Result<(DynamicImage, Vec<String>)> {
+    let psd = Psd::from_bytes(data)?;
+    let img = RgbaImage::from_raw(psd.width(), psd.height(), psd.rgba())
+        .ok_or_else(|| eyre::eyre!("Truncated PSD image"))?;
+
+    let layers = psd
+        .layers()
+        .iter()
+        .map(|layer| layer.name().to_string())
+        .collect();
+    Ok((DynamicImage::ImageRgba8(img), layers))
+}
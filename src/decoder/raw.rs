@@ -0,0 +1,51 @@
+use eyre::Result;
+use image::{DynamicImage, RgbImage};
+use rawler::{decoders::RawDecodeParams, imgop::develop::RawDevelop, rawsource::RawSource};
+use std::path::Path;
+
+/// Develops the sensor data of a camera RAW, falling back to the JPEG preview
+/// embedded by the camera when that fails.
+pub fn decode(path: &Path) -> Result<DynamicImage> {
+    match develop(path) {
+        Ok(img) => Ok(img),
+        Err(e) => preview(path).ok().flatten().ok_or(e),
+    }
+}
+
+fn develop(path: &Path) -> Result<DynamicImage> {
+    let source = RawSource::new(path)?;
+    let decoder = rawler::get_decoder(&source)?;
+    let raw = decoder.raw_image(&source, &RawDecodeParams::default(), false)?;
+    let img = RawDevelop::default()
+        .develop_intermediate(&raw)?
+        .to_dynamic_image()
+        .ok_or_else(|| eyre::eyre!("Cannot develop {}", path.display()))?
+        .to_rgb8();
+
+    convert(img.width(), img.height(), img.into_raw())
+}
+
+/// Returns the largest preview embedded in the file, if any.
+pub fn preview(path: &Path) -> Result<Option<DynamicImage>> {
+    let source = RawSource::new(path)?;
+    let decoder = rawler::get_decoder(&source)?;
+    let params = RawDecodeParams::default();
+
+    let img = match decoder.preview_image(&source, &params)? {
+        Some(img) => img,
+        None => match decoder.thumbnail_image(&source, &params)? {
+            Some(img) => img,
+            None => return Ok(None),
+        },
+    }
+    .to_rgb8();
+
+    convert(img.width(), img.height(), img.into_raw()).map(Some)
+}
+
+/// Rebuilds an image of the `image` version rawler uses.
+fn convert(width: u32, height: u32, buf: Vec<u8>) -> Result<DynamicImage> {
+    RgbImage::from_raw(width, height, buf)
+        .map(DynamicImage::ImageRgb8)
+        .ok_or_else(|| eyre::eyre!("Truncated camera RAW image"))
+}
@@ -0,0 +1,60 @@
+use eyre::Result;
+use image::{DynamicImage, RgbaImage};
+use resvg::{tiny_skia, usvg};
+use std::{
+    path::Path,
+    sync::{Arc, OnceLock},
+};
+
+/// System fonts used by text elements, loaded once.
+fn fontdb() -> Arc<usvg::fontdb::Database> {
+    static FONTDB: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+    FONTDB
+        .get_or_init(|| {
+            let mut fontdb = usvg::fontdb::Database::new();
+            fontdb.load_system_fonts();
+            Arc::new(fontdb)
+        })
+        .clone()
+}
+
+/// Rasterizes an SVG so that it fits `resolution`, keeping its aspect ratio.
+/// Returns the image and the size of the SVG.
+pub fn rasterize(
+    path: &Path,
+    data: &[u8],
+    resolution: (u32, u32),
+) -> Result<(DynamicImage, (u32, u32))> {
+    let options = usvg::Options {
+        resources_dir: path.parent().map(Path::to_path_buf),
+        fontdb: fontdb(),
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_data(data, &options)?;
+
+    let size = tree.size();
+    let scale = (resolution.0 as f32 / size.width()).min(resolution.1 as f32 / size.height());
+    let width = ((size.width() * scale).round() as u32).max(1);
+    let height = ((size.height() * scale).round() as u32).max(1);
+
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| eyre::eyre!("Cannot rasterize at {}x{}", width, height))?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+
+    let pixels = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    let img = RgbaImage::from_raw(width, height, pixels).expect("pixmap matches its size");
+
+    let dimensions = (size.width().round() as u32, size.height().round() as u32);
+    Ok((DynamicImage::ImageRgba8(img), dimensions))
+}
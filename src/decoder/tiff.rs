@@ -0,0 +1,80 @@
+use super::{still, Frame};
+use eyre::Result;
+use image::{DynamicImage, ImageBuffer};
+use std::{fs::File, io::BufReader, path::Path};
+use tiff::{
+    decoder::{Decoder, DecodingResult},
+    ColorType,
+};
+
+/// Decodes every page of a TIFF. Pages in an unsupported color type are skipped.
+pub fn pages(path: &Path) -> Result<Vec<Frame>> {
+    let mut decoder = Decoder::new(BufReader::new(File::open(path)?))?;
+    let mut pages = vec![];
+    let mut error = None;
+
+    loop {
+        match page(&mut decoder) {
+            Ok(img) => pages.push(still(img)),
+            Err(e) => {
+                error.get_or_insert(e);
+            }
+        }
+
+        if !decoder.more_images() {
+            break;
+        }
+        decoder.next_image()?;
+    }
+
+    match error {
+        Some(e) if pages.is_empty() => Err(e),
+        _ => Ok(pages),
+    }
+}
+
+fn page(decoder: &mut Decoder<BufReader<File>>) -> Result<DynamicImage> {
+    let (width, height) = decoder.dimensions()?;
+    let color_type = decoder.colortype()?;
+
+    let img = match (color_type, decoder.read_image()?) {
+        (ColorType::Gray(8), DecodingResult::U8(buf)) => {
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLuma8)
+        }
+        (ColorType::GrayA(8), DecodingResult::U8(buf)) => {
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLumaA8)
+        }
+        (ColorType::RGB(8), DecodingResult::U8(buf)) => {
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgb8)
+        }
+        (ColorType::RGBA(8), DecodingResult::U8(buf)) => {
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgba8)
+        }
+        (ColorType::CMYK(8), DecodingResult::U8(buf)) => {
+            let rgb = buf
+                .chunks_exact(4)
+                .flat_map(|cmyk| {
+                    let k = 255 - cmyk[3] as u16;
+                    let channel = |c: u8| ((255 - c as u16) * k / 255) as u8;
+                    [channel(cmyk[0]), channel(cmyk[1]), channel(cmyk[2])]
+                })
+                .collect();
+            ImageBuffer::from_raw(width, height, rgb).map(DynamicImage::ImageRgb8)
+        }
+        (ColorType::Gray(16), DecodingResult::U16(buf)) => {
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLuma16)
+        }
+        (ColorType::GrayA(16), DecodingResult::U16(buf)) => {
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLumaA16)
+        }
+        (ColorType::RGB(16), DecodingResult::U16(buf)) => {
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgb16)
+        }
+        (ColorType::RGBA(16), DecodingResult::U16(buf)) => {
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgba16)
+        }
+        (color_type, _) => eyre::bail!("Unsupported TIFF color type: {:?}", color_type),
+    };
+
+    img.ok_or_else(|| eyre::eyre!("Truncated TIFF page"))
+}
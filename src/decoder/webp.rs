@@ -0,0 +1,42 @@
+use super::{frame_delay, frame_limit, still, Frame, Pixels};
+use eyre::Result;
+use image::{DynamicImage, ImageBuffer};
+use image_webp::WebPDecoder;
+use std::{fs::File, io::BufReader, path::Path};
+
+/// Decodes a WebP with `image-webp`, which composites animated frames on its canvas.
+pub fn frames(path: &Path) -> Result<Vec<Frame>> {
+    let mut decoder = WebPDecoder::new(BufReader::new(File::open(path)?))?;
+    let (width, height) = decoder.dimensions();
+    let has_alpha = decoder.has_alpha();
+    let size = decoder
+        .output_buffer_size()
+        .ok_or_else(|| eyre::eyre!("{} is too large", path.display()))?;
+    let to_image = |buf: Vec<u8>| {
+        if has_alpha {
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgba8)
+        } else {
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgb8)
+        }
+        .expect("buffer matches the image dimensions")
+    };
+
+    if !decoder.is_animated() {
+        let mut buf = vec![0; size];
+        decoder.read_image(&mut buf)?;
+        return Ok(vec![still(to_image(buf))]);
+    }
+
+    let channels = if has_alpha { 4 } else { 3 };
+    let count = (decoder.num_frames() as usize).min(frame_limit((width, height), channels));
+    (0..count)
+        .map(|_| {
+            let mut buf = vec![0; size];
+            let delay = decoder.read_frame(&mut buf)?;
+            Ok(Frame {
+                pixels: Pixels::Ldr(to_image(buf)),
+                delay: frame_delay(delay),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,65 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::RgbaImage;
+
+/// Encodes `img` as an iTerm2 inline image scaled to `cols`x`rows` cells.
+///
+/// When `move_cursor` is false terminals honoring `doNotMoveCursor` leave the
+/// cursor where the image was placed.
+pub fn encode(img: &RgbaImage, cols: u32, rows: u32, move_cursor: bool) -> Vec<u8> {
+    let png = super::encode_png(img);
+    let mut buf = format!(
+        "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=1",
+        png.len(),
+        cols,
+        rows
+    );
+    if !move_cursor {
+        buf.push_str(";doNotMoveCursor=1");
+    }
+    buf.push(':');
+    buf.push_str(&STANDARD.encode(png));
+    buf.push('\x07');
+
+    buf.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, Rgba};
+
+    fn split(encoded: &[u8]) -> (&str, &str) {
+        let encoded = std::str::from_utf8(encoded).unwrap();
+        let encoded = encoded.strip_suffix('\x07').expect("BEL terminator");
+        encoded.split_once(':').unwrap()
+    }
+
+    #[test]
+    fn encodes_an_inline_png() {
+        let img = RgbaImage::from_pixel(3, 2, Rgba([10, 20, 30, 255]));
+        let encoded = encode(&img, 4, 5, false);
+        let (header, payload) = split(&encoded);
+
+        let png = STANDARD.decode(payload).unwrap();
+        assert_eq!(
+            header,
+            format!(
+                "\x1b]1337;File=inline=1;size={};width=4;height=5;preserveAspectRatio=1;doNotMoveCursor=1",
+                png.len()
+            )
+        );
+
+        let decoded = image::load_from_memory_with_format(&png, image::ImageFormat::Png).unwrap();
+        assert_eq!(decoded.dimensions(), (3, 2));
+        assert_eq!(decoded.get_pixel(2, 1), Rgba([10, 20, 30, 255]));
+    }
+
+    #[test]
+    fn moves_the_cursor_when_asked() {
+        let img = RgbaImage::from_pixel(1, 1, Rgba([0, 0, 0, 255]));
+        let encoded = encode(&img, 1, 1, true);
+        let (header, _) = split(&encoded);
+        assert!(header.ends_with(";width=1;height=1;preserveAspectRatio=1"));
+        assert!(!header.contains("doNotMoveCursor"));
+    }
+}
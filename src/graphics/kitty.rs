@@ -0,0 +1,37 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::RgbaImage;
+
+/// Asks the terminal whether it understands the protocol.
+pub const QUERY: &str = "\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\";
+
+/// Deletes every placement and frees the image data.
+pub const DELETE_ALL: &[u8] = b"\x1b_Ga=d,d=A,q=2\x1b\\";
+
+/// Maximum size of the payload carried by a single escape sequence.
+const CHUNK_SIZE: usize = 4096;
+
+/// Encodes `img` as a kitty "transmit and display" command.
+///
+/// When `move_cursor` is false the cursor stays where the image was placed,
+/// which is what a full screen application wants.
+pub fn encode(img: &RgbaImage, move_cursor: bool) -> Vec<u8> {
+    let payload = STANDARD.encode(super::encode_png(img));
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(CHUNK_SIZE).collect();
+    let mut buf = vec![];
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = if i + 1 < chunks.len() { 1 } else { 0 };
+        if i == 0 {
+            let cursor = if move_cursor { 0 } else { 1 };
+            buf.extend_from_slice(
+                format!("\x1b_Ga=T,f=100,q=2,C={},m={};", cursor, more).as_bytes(),
+            );
+        } else {
+            buf.extend_from_slice(format!("\x1b_Gm={};", more).as_bytes());
+        }
+        buf.extend_from_slice(chunk);
+        buf.extend_from_slice(b"\x1b\\");
+    }
+
+    buf
+}
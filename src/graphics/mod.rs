@@ -0,0 +1,302 @@
+pub mod iterm2;
+pub mod kitty;
+pub mod sixel;
+
+use image::{codecs::png::PngEncoder, ColorType, RgbaImage};
+use std::{
+    env,
+    fmt::{self, Display},
+    io::{self, Write},
+    sync::atomic::{AtomicU32, Ordering},
+};
+use tui::layout::Rect;
+
+/// Fallback cell size in pixels when the terminal does not report it.
+const DEFAULT_CELL_SIZE: (u32, u32) = (10, 20);
+
+static NEXT_GRAPHIC_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Terminal graphics protocols able to display real pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Kitty,
+    Sixel,
+    Iterm2,
+}
+
+impl Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Protocol::Kitty => write!(f, "kitty"),
+            Protocol::Sixel => write!(f, "sixel"),
+            Protocol::Iterm2 => write!(f, "iterm2"),
+        }
+    }
+}
+
+/// An image encoded for a graphics protocol, ready to be written to the terminal.
+#[derive(Debug, Clone)]
+pub struct Graphic {
+    pub id: u32,
+    pub protocol: Protocol,
+    pub data: Vec<u8>,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Graphic {
+    /// Encodes `img` to be shown over `cols`x`rows` cells.
+    pub fn new(protocol: Protocol, img: &RgbaImage, cols: u32, rows: u32) -> Self {
+        Self {
+            id: NEXT_GRAPHIC_ID.fetch_add(1, Ordering::Relaxed),
+            protocol,
+            data: encode(protocol, img, (cols, rows), false),
+            width: cols as u16,
+            height: rows as u16,
+        }
+    }
+}
+
+/// Keeps track of the graphic currently shown in the TUI so that it is only
+/// redrawn when the image or the layout changes.
+#[derive(Debug, Default)]
+pub struct Placer {
+    placed: Option<Placement>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Placement {
+    id: u32,
+    protocol: Protocol,
+    area: Rect,
+    rect: Rect,
+}
+
+impl Placer {
+    /// Removes the current placement unless it is graphic `id` shown in `area`.
+    ///
+    /// Call it before drawing the UI: sixel and iTerm2 images live in the text
+    /// layer and are erased by overwriting their cells, which must not clobber
+    /// the UI.
+    pub fn retain<W: Write>(&mut self, w: &mut W, id: Option<u32>, area: Rect) -> io::Result<()> {
+        match self.placed {
+            Some(placed) if Some(placed.id) != id || placed.area != area => self.clear(w),
+            _ => Ok(()),
+        }
+    }
+
+    /// Shows `graphic` horizontally centered at the top of `area`, removing the
+    /// previous placement first. Passing `None` only removes it.
+    ///
+    /// Call it after drawing the UI so that the graphic ends up on top.
+    pub fn update<W: Write>(
+        &mut self,
+        w: &mut W,
+        graphic: Option<&Graphic>,
+        area: Rect,
+    ) -> io::Result<()> {
+        let graphic = match graphic {
+            Some(graphic) => graphic,
+            None => return self.clear(w),
+        };
+        if let Some(placed) = self.placed {
+            if placed.id == graphic.id && placed.area == area {
+                return Ok(());
+            }
+        }
+
+        self.clear(w)?;
+
+        let width = std::cmp::min(graphic.width, area.width);
+        let height = std::cmp::min(graphic.height, area.height);
+        let x = area.x + (area.width - width) / 2;
+        crossterm::queue!(w, crossterm::cursor::MoveTo(x, area.y))?;
+        w.write_all(&graphic.data)?;
+        w.flush()?;
+
+        self.placed = Some(Placement {
+            id: graphic.id,
+            protocol: graphic.protocol,
+            area,
+            rect: Rect::new(x, area.y, width, height),
+        });
+
+        Ok(())
+    }
+
+    /// Removes the current placement, if any.
+    pub fn clear<W: Write>(&mut self, w: &mut W) -> io::Result<()> {
+        if let Some(placed) = self.placed.take() {
+            match placed.protocol {
+                Protocol::Kitty => w.write_all(kitty::DELETE_ALL)?,
+                Protocol::Sixel | Protocol::Iterm2 => erase(w, placed.rect)?,
+            }
+            w.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Overwrites the cells of `rect` with blanks.
+fn erase<W: Write>(w: &mut W, rect: Rect) -> io::Result<()> {
+    crossterm::queue!(
+        w,
+        crossterm::style::SetAttribute(crossterm::style::Attribute::Reset)
+    )?;
+    for y in rect.top()..rect.bottom() {
+        crossterm::queue!(w, crossterm::cursor::MoveTo(rect.x, y))?;
+        w.write_all(" ".repeat(rect.width as usize).as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Detects a graphics protocol supported by the current terminal.
+pub fn detect() -> Option<Protocol> {
+    if env::var_os("KITTY_WINDOW_ID").is_some()
+        || env::var("TERM").is_ok_and(|term| term.contains("kitty"))
+        || env::var("TERM_PROGRAM").is_ok_and(|p| p == "ghostty")
+    {
+        return Some(Protocol::Kitty);
+    }
+    if env::var("TERM_PROGRAM").is_ok_and(|p| p == "iTerm.app" || p == "WezTerm")
+        || env::var("LC_TERMINAL").is_ok_and(|t| t == "iTerm2")
+    {
+        return Some(Protocol::Iterm2);
+    }
+
+    let response = query(kitty::QUERY)?;
+    if response.contains("_Gi=31;OK") {
+        Some(Protocol::Kitty)
+    } else if sixel::is_supported(&response) {
+        Some(Protocol::Sixel)
+    } else {
+        None
+    }
+}
+
+/// Returns the size of a terminal cell in pixels.
+pub fn cell_size() -> (u32, u32) {
+    sys::cell_size().unwrap_or(DEFAULT_CELL_SIZE)
+}
+
+/// Encodes `img` for `protocol`, covering `cells` columns and rows.
+///
+/// When `move_cursor` is false the cursor is left where the image starts,
+/// otherwise it ends up after the image like after printed text.
+pub fn encode(
+    protocol: Protocol,
+    img: &RgbaImage,
+    cells: (u32, u32),
+    move_cursor: bool,
+) -> Vec<u8> {
+    match protocol {
+        Protocol::Kitty => kitty::encode(img, move_cursor),
+        Protocol::Sixel => sixel::encode(img),
+        Protocol::Iterm2 => iterm2::encode(img, cells.0, cells.1, move_cursor),
+    }
+}
+
+/// Encodes `img` as PNG.
+pub fn encode_png(img: &RgbaImage) -> Vec<u8> {
+    let mut buf = vec![];
+    PngEncoder::new(&mut buf)
+        .encode(img.as_raw(), img.width(), img.height(), ColorType::Rgba8)
+        .expect("encoding to memory should not fail");
+    buf
+}
+
+/// Sends `request` followed by a primary device attributes request, which every
+/// terminal answers, and returns everything received until that answer.
+pub(crate) fn query(request: &str) -> Option<String> {
+    sys::query(request)
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::io::{self, Write};
+
+    const QUERY_TIMEOUT_MS: i32 = 100;
+
+    pub fn cell_size() -> Option<(u32, u32)> {
+        let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+        if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } != 0 {
+            return None;
+        }
+        if size.ws_col == 0 || size.ws_row == 0 || size.ws_xpixel == 0 || size.ws_ypixel == 0 {
+            return None;
+        }
+
+        Some((
+            (size.ws_xpixel / size.ws_col) as u32,
+            (size.ws_ypixel / size.ws_row) as u32,
+        ))
+    }
+
+    pub fn query(request: &str) -> Option<String> {
+        if unsafe {
+            libc::isatty(libc::STDIN_FILENO) == 0 || libc::isatty(libc::STDOUT_FILENO) == 0
+        } {
+            return None;
+        }
+
+        crossterm::terminal::enable_raw_mode().ok()?;
+        let response = read_response(request);
+        crossterm::terminal::disable_raw_mode().ok()?;
+
+        response
+            .ok()
+            .map(|r| String::from_utf8_lossy(&r).into_owned())
+    }
+
+    fn read_response(request: &str) -> io::Result<Vec<u8>> {
+        let mut stdout = io::stdout();
+        write!(stdout, "{}\x1b[c", request)?;
+        stdout.flush()?;
+
+        let mut response = vec![];
+        loop {
+            let mut fd = libc::pollfd {
+                fd: libc::STDIN_FILENO,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            if unsafe { libc::poll(&mut fd, 1, QUERY_TIMEOUT_MS) } <= 0 {
+                break;
+            }
+
+            let mut buf = [0u8; 256];
+            let n = unsafe { libc::read(libc::STDIN_FILENO, buf.as_mut_ptr().cast(), buf.len()) };
+            if n <= 0 {
+                break;
+            }
+            response.extend_from_slice(&buf[..n as usize]);
+
+            if is_device_attributes_end(&response) {
+                break;
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// The primary device attributes answer looks like `ESC [ ? 6 2 ; 4 c`.
+    fn is_device_attributes_end(response: &[u8]) -> bool {
+        response
+            .windows(3)
+            .rposition(|w| w == b"\x1b[?")
+            .is_some_and(|start| response[start..].ends_with(b"c"))
+    }
+}
+
+#[cfg(not(unix))]
+mod sys {
+    pub fn cell_size() -> Option<(u32, u32)> {
+        None
+    }
+
+    pub fn query(_request: &str) -> Option<String> {
+        None
+    }
+}
@@ -0,0 +1,206 @@
+use color_quant::NeuQuant;
+use image::RgbaImage;
+use std::{collections::BTreeSet, fmt::Write};
+
+/// Number of color registers used by the encoder.
+const PALETTE_SIZE: usize = 256;
+
+/// Sampling factor handed to NeuQuant, 1 is the slowest and most accurate.
+const SAMPLE_FACTOR: i32 = 10;
+
+/// Primary device attribute advertised by terminals supporting sixel graphics.
+const SIXEL_ATTRIBUTE: &str = "4";
+
+/// Returns whether a primary device attributes answer advertises sixel support.
+pub fn is_supported(response: &str) -> bool {
+    response
+        .rsplit("\x1b[?")
+        .next()
+        .and_then(|attributes| attributes.split('c').next())
+        .is_some_and(|attributes| attributes.split(';').any(|a| a == SIXEL_ATTRIBUTE))
+}
+
+/// Encodes `img` as a sixel sequence.
+///
+/// Colors are quantized to a palette of at most 256 registers and fully
+/// transparent pixels are left untouched.
+pub fn encode(img: &RgbaImage) -> Vec<u8> {
+    let (width, height) = img.dimensions();
+    let mut buf = String::new();
+    write!(buf, "\x1bP0;1;0q\"1;1;{};{}", width, height).unwrap();
+
+    let (palette, indices) = quantize(img);
+    for (i, color) in palette.iter().enumerate() {
+        write!(
+            buf,
+            "#{};2;{};{};{}",
+            i,
+            percent(color[0]),
+            percent(color[1]),
+            percent(color[2])
+        )
+        .unwrap();
+    }
+
+    for top in (0..height).step_by(6) {
+        let band_height = std::cmp::min(6, height - top);
+        let band = &indices[(top * width) as usize..((top + band_height) * width) as usize];
+
+        let mut colors: Vec<usize> = band.iter().flatten().copied().collect();
+        colors.sort_unstable();
+        colors.dedup();
+
+        for (n, &color) in colors.iter().enumerate() {
+            if n > 0 {
+                buf.push('$');
+            }
+            write!(buf, "#{}", color).unwrap();
+
+            let sixels: Vec<u8> = (0..width)
+                .map(|x| {
+                    (0..band_height)
+                        .filter(|&dy| band[(dy * width + x) as usize] == Some(color))
+                        .fold(0, |bits, dy| bits | 1 << dy)
+                })
+                .collect();
+            push_sixels(&mut buf, &sixels);
+        }
+        buf.push('-');
+    }
+
+    buf.push_str("\x1b\\");
+    buf.into_bytes()
+}
+
+/// Maps every opaque pixel of `img` to a palette entry.
+///
+/// Images with few colors keep them exactly, others go through NeuQuant.
+fn quantize(img: &RgbaImage) -> (Vec<[u8; 3]>, Vec<Option<usize>>) {
+    let mut palette: Vec<[u8; 3]> = img
+        .pixels()
+        .filter(|p| p[3] != 0)
+        .map(|p| [p[0], p[1], p[2]])
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let indices = if palette.len() <= PALETTE_SIZE {
+        img.pixels()
+            .map(|p| (p[3] != 0).then(|| palette.binary_search(&[p[0], p[1], p[2]]).unwrap()))
+            .collect()
+    } else {
+        let opaque: Vec<u8> = img
+            .pixels()
+            .filter(|p| p[3] != 0)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect();
+        let quant = NeuQuant::new(SAMPLE_FACTOR, PALETTE_SIZE, &opaque);
+        palette = quant
+            .color_map_rgb()
+            .chunks(3)
+            .map(|c| [c[0], c[1], c[2]])
+            .collect();
+
+        img.pixels()
+            .map(|p| (p[3] != 0).then(|| quant.index_of(&[p[0], p[1], p[2], 255])))
+            .collect()
+    };
+
+    (palette, indices)
+}
+
+/// Appends sixel characters run-length encoded, dropping the empty tail.
+fn push_sixels(buf: &mut String, sixels: &[u8]) {
+    let len = sixels.iter().rposition(|&s| s != 0).map_or(0, |i| i + 1);
+    let mut i = 0;
+
+    while i < len {
+        let run = sixels[i..len]
+            .iter()
+            .take_while(|&&s| s == sixels[i])
+            .count();
+        let ch = char::from(b'?' + sixels[i]);
+        if run > 3 {
+            write!(buf, "!{}{}", run, ch).unwrap();
+        } else {
+            (0..run).for_each(|_| buf.push(ch));
+        }
+        i += run;
+    }
+}
+
+/// Converts a color channel to the 0-100 range used by sixel palettes.
+fn percent(value: u8) -> u32 {
+    (value as u32 * 100 + 127) / 255
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+    use std::{env, fs, path::PathBuf};
+
+    /// Compares `actual` with a golden file, which is written instead when
+    /// `UPDATE_GOLDEN` is set.
+    fn assert_golden(name: &str, actual: &[u8]) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/sixel")
+            .join(name);
+        if env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, actual).unwrap();
+        }
+        let expected = fs::read(&path).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(actual),
+            String::from_utf8_lossy(&expected),
+            "{}",
+            name
+        );
+    }
+
+    #[test]
+    fn encodes_exact_palette() {
+        let img = RgbaImage::from_fn(3, 2, |x, y| match (x + y) % 3 {
+            0 => Rgba([255, 0, 0, 255]),
+            1 => Rgba([0, 255, 0, 255]),
+            _ => Rgba([0, 0, 255, 255]),
+        });
+        assert_golden("exact.six", &encode(&img));
+    }
+
+    #[test]
+    fn encodes_many_colors_with_neuquant() {
+        let img = RgbaImage::from_fn(20, 16, |x, y| {
+            Rgba([(x * 12) as u8, (y * 16) as u8, ((x + y) * 7) as u8, 255])
+        });
+        let (palette, _) = quantize(&img);
+        assert_eq!(palette.len(), PALETTE_SIZE);
+        assert_golden("neuquant.six", &encode(&img));
+    }
+
+    #[test]
+    fn skips_transparent_pixels() {
+        let img = RgbaImage::from_fn(4, 1, |x, _| match x {
+            1 | 2 => Rgba([255, 255, 255, 0]),
+            _ => Rgba([255, 255, 255, 255]),
+        });
+        assert_golden("transparent.six", &encode(&img));
+    }
+
+    #[test]
+    fn encodes_partial_bands() {
+        let img = RgbaImage::from_pixel(5, 8, Rgba([0, 0, 0, 255]));
+        assert_golden("partial_band.six", &encode(&img));
+    }
+
+    #[test]
+    fn parses_device_attributes() {
+        assert!(is_supported("\x1b[?62;4;22c"));
+        assert!(is_supported("\x1b[?4c"));
+        assert!(is_supported("\x1b_Gi=31;EINVAL\x1b\\\x1b[?64;4c"));
+        assert!(!is_supported("\x1b[?62;22c"));
+        assert!(!is_supported("\x1b[?64;14c"));
+        assert!(!is_supported(""));
+    }
+}
@@ -1,78 +1,67 @@
-use crate::utils::ImageMode;
-use ansi_rgb::Colorable;
-use image::{DynamicImage, GenericImageView, LumaA, Rgba};
-use rgb::RGB8;
+use crate::{
+    color_vision,
+    decoder::Pixels,
+    graphics,
+    renderer::{self, color::ColorDepth, Rendered, Renderer},
+    tone_map::ToneMap,
+    utils::ImageMode,
+};
+use image::DynamicImage;
+use std::io::{stdout, Write};
 
-pub fn image_fit_size(img: &DynamicImage, term_w: u32, term_h: u32) -> (u32, u32) {
-    let (img_width, img_height) = img.dimensions();
-    let (w, h) = get_dimensions(img_width, img_height, term_w, term_h);
-    let h = if h == term_h { h - 1 } else { h };
-    (w, h)
-}
-
-pub fn get_dimensions(width: u32, height: u32, bound_width: u32, bound_height: u32) -> (u32, u32) {
-    let bound_height = 2 * bound_height;
-
-    if width <= bound_width && height <= bound_height {
-        return (width, std::cmp::max(1, height / 2 + height % 2));
+/// Returns the image to display for decoded pixels, tone mapping high dynamic
+/// range ones.
+pub fn to_display_image(pixels: &Pixels, tone_map: ToneMap) -> DynamicImage {
+    match pixels {
+        Pixels::Ldr(img) => img.clone(),
+        Pixels::Hdr(img) => tone_map.apply(img),
     }
+}
 
-    let ratio = width * bound_height;
-    let nratio = bound_width * height;
-
-    let use_width = nratio <= ratio;
-    let intermediate = if use_width {
-        height * bound_width / width
-    } else {
-        width * bound_height / height
-    };
-
-    if use_width {
-        (bound_width, std::cmp::max(1, intermediate / 2))
-    } else {
-        (intermediate, std::cmp::max(1, bound_height / 2))
+/// Converts `img` to the colors used by `mode`.
+pub fn apply_image_mode(img: DynamicImage, mode: &ImageMode) -> DynamicImage {
+    match mode {
+        ImageMode::Rgba => img,
+        ImageMode::GrayScale => DynamicImage::ImageLumaA8(img.to_luma_alpha8()),
+        ImageMode::Simulate(deficiency) => {
+            let mut img = img.to_rgba8();
+            color_vision::simulate(&mut img, *deficiency);
+            DynamicImage::ImageRgba8(img)
+        }
     }
 }
 
-pub fn print_term_image(img: DynamicImage, mode: ImageMode) {
-    let size = crossterm::terminal::size().unwrap();
-    let (w, h) = image_fit_size(&img, size.0 as u32, size.1 as u32);
-    let imgbuf = img.resize_exact(w, h, image::imageops::FilterType::Triangle);
-    let (width, height) = imgbuf.dimensions();
+/// Returns the columns and rows an image can be printed in.
+pub fn print_area() -> (u32, u32) {
+    // Not a terminal when writing to a log, fall back to a common size.
+    let (cols, rows) = crossterm::terminal::size().unwrap_or((80, 24));
 
-    match mode {
-        ImageMode::Rgba => {
-            let imgbuf = imgbuf.to_rgba8();
-            for y in 0..height {
-                for x in 0..width {
-                    let pixel = imgbuf.get_pixel(x, y);
-                    let Rgba(data) = *pixel;
+    // Leave a line for the shell prompt.
+    (cols as u32, rows.saturating_sub(1) as u32)
+}
 
-                    if data[3] == 0 {
-                        print!(" ");
-                    } else {
-                        let bg = RGB8::new(data[0], data[1], data[2]);
-                        print!("{}", " ".bg(bg));
-                    }
-                }
-                println!();
-            }
-        }
-        ImageMode::GrayScale => {
-            let imgbuf = imgbuf.to_luma_alpha8();
-            for y in 0..height {
-                for x in 0..width {
-                    let pixel = imgbuf.get_pixel(x, y);
-                    let LumaA(data) = *pixel;
+pub fn print_term_image(
+    img: DynamicImage,
+    mode: ImageMode,
+    renderer: &dyn Renderer,
+    depth: ColorDepth,
+) {
+    let (cols, rows) = print_area();
+    let img = apply_image_mode(img, &mode);
 
-                    if data[1] == 0 {
-                        print!(" ");
-                    } else {
-                        print!("{}", " ".bg(RGB8::new(data[0], data[0], data[0])));
-                    }
-                }
-                println!();
-            }
+    match renderer.render(&img, cols, rows) {
+        Rendered::Cells(cells) => print!("{}", renderer::to_ansi(&cells, depth)),
+        Rendered::Pixels {
+            protocol,
+            img,
+            cols,
+            rows,
+        } => {
+            let mut stdout = stdout();
+            stdout
+                .write_all(&graphics::encode(protocol, &img, (cols, rows), true))
+                .unwrap();
+            println!();
         }
     }
 }
@@ -1,7 +1,7 @@
 use super::{key::Key, InputEvent};
 use std::{
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc,
     },
     time::Duration,
@@ -10,17 +10,22 @@ use std::{
 pub struct Events {
     rx: tokio::sync::mpsc::Receiver<InputEvent>,
     stop_capture: Arc<AtomicBool>,
+    /// In milliseconds.
+    tick_rate: Arc<AtomicU64>,
 }
 
 impl Events {
     pub fn new(tick_rate: Duration) -> Events {
         let (tx, rx) = tokio::sync::mpsc::channel(1000);
         let stop_capture = Arc::new(AtomicBool::new(false));
+        let tick_rate = Arc::new(AtomicU64::new(tick_rate.as_millis() as u64));
 
         let event_tx = tx.clone();
         let event_stop_capture = stop_capture.clone();
+        let event_tick_rate = tick_rate.clone();
         tokio::spawn(async move {
             loop {
+                let tick_rate = Duration::from_millis(event_tick_rate.load(Ordering::Relaxed));
                 if crossterm::event::poll(tick_rate).unwrap() {
                     if let crossterm::event::Event::Key(key) = crossterm::event::read().unwrap() {
                         let key = Key::from(key);
@@ -34,7 +39,17 @@ impl Events {
             }
         });
 
-        Events { rx, stop_capture }
+        Events {
+            rx,
+            stop_capture,
+            tick_rate,
+        }
+    }
+
+    /// Changes the time between ticks, from the next one on.
+    pub fn set_tick_rate(&self, tick_rate: Duration) {
+        self.tick_rate
+            .store(tick_rate.as_millis() as u64, Ordering::Relaxed)
     }
 
     pub async fn next(&mut self) -> InputEvent {
@@ -1,30 +1,78 @@
 use crate::{
-    app::{state::ImageInfo, App},
-    image::image_fit_size,
+    adjust::Adjustments,
+    app::{
+        state::{ImageInfo, RenderedFrame},
+        App,
+    },
+    decoder::{self, DecodedImage, Format, Pixels},
+    graphics::Graphic,
+    image::{apply_image_mode, to_display_image},
     io::IoEvent,
+    renderer::{self, color::ColorDepth, scaling::Filter, Rendered, Renderer},
+    tone_map::ToneMap,
+    transform::Transform,
     utils::ImageMode,
+    viewport::Viewport,
 };
 use eyre::Result;
-use image::{GenericImageView, LumaA, Rgba};
-use std::sync::Arc;
-use tui::{
-    style::{Color, Style},
-    text::{Line, Span},
-};
+use std::{path::PathBuf, sync::Arc, time::Duration};
+use tokio::sync::mpsc::Receiver;
+
+/// How long a camera RAW stays shown before its sensor data is developed.
+const DEVELOP_DELAY: Duration = Duration::from_millis(500);
 
 pub struct IoAsyncHandler<'a> {
     app: Arc<tokio::sync::Mutex<App<'a>>>,
+    /// The image shown in the panel, kept to render it again.
+    decoded: Option<Arc<DecodedImage>>,
+    /// The file the image was decoded from.
+    path: Option<PathBuf>,
+    /// The panel resolution the image was decoded for.
+    resolution: Option<(u32, u32)>,
+    /// Whether the image is the preview of a camera RAW still to be developed.
+    undeveloped: bool,
 }
 
 impl<'a> IoAsyncHandler<'a> {
     pub fn new(app: Arc<tokio::sync::Mutex<App<'a>>>) -> Self {
-        Self { app }
+        Self {
+            app,
+            decoded: None,
+            path: None,
+            resolution: None,
+            undeveloped: false,
+        }
+    }
+
+    /// Handles the events of the app until it quits. A camera RAW is developed
+    /// once it stayed shown for a while, so that skimming through them only
+    /// decodes their previews.
+    pub async fn run(&mut self, mut rx: Receiver<IoEvent>) {
+        loop {
+            let io_event = if self.undeveloped {
+                match tokio::time::timeout(DEVELOP_DELAY, rx.recv()).await {
+                    Ok(io_event) => io_event,
+                    Err(_) => {
+                        let _ = self.do_develop_image().await;
+                        continue;
+                    }
+                }
+            } else {
+                rx.recv().await
+            };
+
+            match io_event {
+                Some(io_event) => self.handle_io_event(io_event).await,
+                None => break,
+            }
+        }
     }
 
     pub async fn handle_io_event(&mut self, io_event: IoEvent) {
         let _ = match io_event {
             IoEvent::Initialize(path) => self.do_initialize(&path).await,
             IoEvent::LoadImage => self.do_load_image().await,
+            IoEvent::RenderImage => self.do_render_image().await,
         };
 
         let mut app = self.app.lock().await;
@@ -39,8 +87,6 @@ impl<'a> IoAsyncHandler<'a> {
     }
 
     async fn do_load_image(&mut self) -> Result<()> {
-        let result = Arc::new(tokio::sync::Mutex::new(vec![]));
-
         let opt_index = {
             let app = self.app.lock().await;
             app.state.get_index()
@@ -54,26 +100,47 @@ impl<'a> IoAsyncHandler<'a> {
             }
         };
 
-        let opt_term_size = {
+        let opt_resolution = {
             let app = self.app.lock().await;
-            app.state.get_term_size()
-        };
-
-        let mode = {
-            let app = self.app.lock().await;
-            app.config.image_mode.clone()
+            app.resolution()
         };
 
         {
             if let Some(path) = opt_path {
-                if let Some(term_size) = opt_term_size {
-                    let img = tokio::task::block_in_place(|| image::open(&path))?;
+                if let Some(resolution) = opt_resolution {
+                    // Animations can take a while to decode, keep it off the async workers.
+                    // Camera RAWs show their preview until they are developed.
+                    let decoded = {
+                        let path = path.clone();
+                        tokio::task::spawn_blocking(move || match decoder::open_preview(&path) {
+                            Some(preview) => Ok((preview, true)),
+                            None => {
+                                decoder::open(&path, resolution).map(|decoded| (decoded, false))
+                            }
+                        })
+                        .await?
+                    };
+                    let decoded = match decoded {
+                        Ok((decoded, undeveloped)) => {
+                            self.undeveloped = undeveloped;
+                            decoded
+                        }
+                        Err(e) => {
+                            let mut app = self.app.lock().await;
+                            app.state.mark_undecodable(&path);
+                            app.state.set_image_error(e.to_string());
+                            self.decoded = None;
+                            self.path = None;
+                            self.resolution = None;
+                            self.undeveloped = false;
+                            return Err(e);
+                        }
+                    };
                     let name = path
                         .file_name()
                         .unwrap_or_default()
-                        .to_str()
-                        .unwrap()
-                        .to_string();
+                        .to_string_lossy()
+                        .into_owned();
                     let size = match path.metadata() {
                         Ok(metadata) => metadata.len(),
                         Err(_) => 0,
@@ -81,67 +148,226 @@ impl<'a> IoAsyncHandler<'a> {
                     let info = ImageInfo {
                         name,
                         size,
-                        dimensions: img.dimensions(),
+                        dimensions: decoded.dimensions,
+                        format: decoded.format,
+                        hdr: decoded
+                            .frames
+                            .iter()
+                            .any(|frame| matches!(frame.pixels, Pixels::Hdr(_))),
+                        layers: decoded.layers.clone(),
+                        orientation: decoded.orientation,
                     };
 
-                    let (w, h) = image_fit_size(&img, term_size.width, term_size.height);
-                    let imgbuf = img.resize_exact(w, h, image::imageops::FilterType::Triangle);
-                    let (width, height) = imgbuf.dimensions();
-
-                    let mut r = result.lock().await;
-
-                    match mode {
-                        ImageMode::Rgba => {
-                            let imgbuf = imgbuf.to_rgba8();
-                            for y in 0..height {
-                                let mut line = vec![];
-                                for x in 0..width {
-                                    let pixel = imgbuf.get_pixel(x, y);
-                                    let Rgba(data) = *pixel;
-
-                                    if data[3] == 0 {
-                                        line.push(Span::from(" "));
-                                    } else {
-                                        line.push(Span::styled(
-                                            " ",
-                                            Style::default()
-                                                .bg(Color::Rgb(data[0], data[1], data[2])),
-                                        ));
-                                    }
-                                }
-                                (*r).push(Line::from(line))
-                            }
-                        }
-                        ImageMode::GrayScale => {
-                            let imgbuf = imgbuf.to_luma_alpha8();
-                            for y in 0..height {
-                                let mut line = vec![];
-                                for x in 0..width {
-                                    let pixel = imgbuf.get_pixel(x, y);
-                                    let LumaA(data) = *pixel;
-
-                                    if data[1] == 0 {
-                                        line.push(Span::from(" "));
-                                    } else {
-                                        line.push(Span::styled(
-                                            " ",
-                                            Style::default()
-                                                .bg(Color::Rgb(data[0], data[0], data[0])),
-                                        ));
-                                    }
-                                }
-                                (*r).push(Line::from(line))
-                            }
-                        }
-                    }
-
-                    let mut app = self.app.lock().await;
-                    app.state.set_current_image_info(info);
-                    app.state.set_current_image((*r).clone());
+                    let settings = {
+                        let mut app = self.app.lock().await;
+                        app.state.set_current_image_info(info);
+                        RenderSettings::new(&app)
+                    };
+                    self.decoded = Some(Arc::new(decoded));
+                    self.path = Some(path);
+                    self.resolution = Some(resolution);
+                    self.render(settings, None).await;
                 }
             }
         }
 
         Ok(())
     }
+
+    /// Replaces the preview of a camera RAW with its developed sensor data,
+    /// keeping the preview when that fails.
+    async fn do_develop_image(&mut self) -> Result<()> {
+        self.undeveloped = false;
+        let (Some(path), Some(resolution)) = (self.path.clone(), self.resolution) else {
+            return Ok(());
+        };
+
+        let decoded =
+            tokio::task::spawn_blocking(move || decoder::open(&path, resolution)).await??;
+        let (settings, index) = {
+            let mut app = self.app.lock().await;
+            if let Some(mut info) = app.state.get_current_image_info() {
+                info.dimensions = decoded.dimensions;
+                app.state.set_current_image_info(info);
+            }
+            (RenderSettings::new(&app), app.state.get_frame_index())
+        };
+        self.decoded = Some(Arc::new(decoded));
+        self.render(settings, index).await;
+
+        Ok(())
+    }
+
+    async fn do_render_image(&mut self) -> Result<()> {
+        self.rasterize_zoomed().await;
+
+        let (settings, index) = {
+            let app = self.app.lock().await;
+            (RenderSettings::new(&app), app.state.get_frame_index())
+        };
+        self.render(settings, index).await;
+
+        Ok(())
+    }
+
+    /// Rasterizes a vector image again when it is zoomed in beyond the size it
+    /// was rasterized at or the panel resolution changed with the renderer,
+    /// keeping the current raster when that fails.
+    async fn rasterize_zoomed(&mut self) {
+        let (Some(decoded), Some(path)) = (self.decoded.as_ref(), self.path.clone()) else {
+            return;
+        };
+        if decoded.format != Format::Svg {
+            return;
+        }
+
+        let (size, resolution) = {
+            let app = self.app.lock().await;
+            let Some(resolution) = app.resolution() else {
+                return;
+            };
+            // Sizes are swapped back and forth for quarter turns.
+            let transform = app.state.get_image_transform();
+            let size = app
+                .state
+                .get_viewport()
+                .raster_size(transform.dimensions(decoded.dimensions), resolution);
+            (transform.dimensions(size), resolution)
+        };
+        let rasterized = decoded.frames[0].pixels.dimensions();
+        let zoomed = size.0 > rasterized.0 || size.1 > rasterized.1;
+        if !zoomed && self.resolution == Some(resolution) {
+            return;
+        }
+
+        if let Ok(Ok(decoded)) =
+            tokio::task::spawn_blocking(move || decoder::open(&path, size)).await
+        {
+            self.decoded = Some(Arc::new(decoded));
+            self.resolution = Some(resolution);
+        }
+    }
+
+    /// Renders the frames of the decoded image off the async workers, without
+    /// holding the lock of the app. The frame at `index` is shown first so that
+    /// settings apply at once to long animations, then the other frames replace
+    /// those of the current image, or of a new one when there is no `index`.
+    async fn render(&self, settings: Option<RenderSettings>, index: Option<usize>) {
+        let (Some(settings), Some(decoded)) = (settings, self.decoded.clone()) else {
+            return;
+        };
+        let shown = index.unwrap_or(0).min(decoded.frames.len() - 1);
+
+        let rendered = {
+            let decoded = Arc::clone(&decoded);
+            tokio::task::spawn_blocking(move || {
+                let frame = settings.render_frame(&decoded, shown);
+                (settings, frame)
+            })
+            .await
+        };
+        let Ok((settings, frame)) = rendered else {
+            return;
+        };
+
+        if decoded.frames.len() == 1 {
+            let mut app = self.app.lock().await;
+            match index {
+                Some(_) => app.state.update_current_image(vec![frame]),
+                None => app.state.set_current_image(vec![frame]),
+            }
+            return;
+        }
+
+        {
+            let mut app = self.app.lock().await;
+            match index {
+                Some(index) => app.state.update_current_frame(index, frame.clone()),
+                None => app.state.set_current_image(vec![frame.clone()]),
+            }
+        }
+        if let Ok(frames) =
+            tokio::task::spawn_blocking(move || settings.render(&decoded, shown, frame)).await
+        {
+            let mut app = self.app.lock().await;
+            app.state.update_current_image(frames);
+        }
+    }
+}
+
+/// The settings of the app the image is rendered with.
+struct RenderSettings {
+    renderer: Box<dyn Renderer + Send>,
+    depth: ColorDepth,
+    mode: ImageMode,
+    tone_map: ToneMap,
+    adjustments: Adjustments,
+    viewport: Viewport,
+    transform: Transform,
+    filter: Filter,
+    cells: (u32, u32),
+    resolution: (u32, u32),
+}
+
+impl RenderSettings {
+    fn new(app: &App) -> Option<Self> {
+        let options = &app.config.render_options;
+
+        Some(Self {
+            renderer: app.config.renderer.renderer(options),
+            depth: options.colors.depth,
+            mode: app.config.image_mode,
+            tone_map: app.config.tone_map,
+            adjustments: app.config.adjustments,
+            viewport: app.state.get_viewport(),
+            transform: app.state.get_image_transform(),
+            filter: options.scaling.filter,
+            cells: app.image_cells()?,
+            resolution: app.resolution()?,
+        })
+    }
+
+    /// Renders the frames of `decoded`, but the one at `index` already rendered
+    /// as `shown`.
+    fn render(
+        &self,
+        decoded: &DecodedImage,
+        index: usize,
+        shown: RenderedFrame<'static>,
+    ) -> Vec<RenderedFrame<'static>> {
+        let mut frames: Vec<_> = (0..decoded.frames.len())
+            .filter(|i| *i != index)
+            .map(|i| self.render_frame(decoded, i))
+            .collect();
+        frames.insert(index, shown);
+        frames
+    }
+
+    /// Renders the frame of `decoded` at `index`.
+    fn render_frame(&self, decoded: &DecodedImage, index: usize) -> RenderedFrame<'static> {
+        let frame = &decoded.frames[index];
+        let dimensions = self.transform.dimensions(decoded.dimensions);
+        let (cols, rows) = self.cells;
+
+        let img = to_display_image(&frame.pixels, self.tone_map);
+        let img = self.transform.apply(self.adjustments.apply(img));
+        let img = self
+            .viewport
+            .crop(img, dimensions, self.resolution, self.filter);
+        let img = apply_image_mode(img, &self.mode);
+        let (lines, graphic) = match self.renderer.render(&img, cols, rows) {
+            Rendered::Cells(cells) => (renderer::to_lines(&cells, self.depth), None),
+            Rendered::Pixels {
+                protocol,
+                img,
+                cols,
+                rows,
+            } => (vec![], Some(Graphic::new(protocol, &img, cols, rows))),
+        };
+        RenderedFrame {
+            lines,
+            graphic,
+            delay: frame.delay,
+        }
+    }
 }
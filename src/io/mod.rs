@@ -4,4 +4,6 @@ pub mod handler;
 pub enum IoEvent {
     Initialize(String),
     LoadImage,
+    /// Renders the loaded image again with the current settings.
+    RenderImage,
 }
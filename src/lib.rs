@@ -1,11 +1,21 @@
+pub mod adjust;
 pub mod app;
+pub mod color_vision;
+pub mod config;
+pub mod decoder;
+pub mod graphics;
 pub mod image;
 pub mod inputs;
 pub mod io;
+pub mod renderer;
+pub mod tone_map;
+pub mod transform;
 pub mod utils;
+pub mod viewport;
 
 use crate::{
     app::{ui, App, AppReturn},
+    graphics::{Graphic, Placer},
     io::IoEvent,
 };
 use crossterm::{
@@ -28,6 +38,8 @@ pub async fn start_ui<'a>(app: &Arc<tokio::sync::Mutex<App<'a>>>, path: String)
     terminal.hide_cursor()?;
 
     let tick_rate = Duration::from_millis(100);
+    // Short enough to play animations smoothly.
+    let animation_tick_rate = Duration::from_millis(30);
     let mut events = Events::new(tick_rate);
 
     {
@@ -35,11 +47,22 @@ pub async fn start_ui<'a>(app: &Arc<tokio::sync::Mutex<App<'a>>>, path: String)
         app.dispatch(IoEvent::Initialize(path)).await;
     }
 
+    let mut placer = Placer::default();
+
     loop {
         let mut app = app.lock().await;
 
+        if let Some(area) = app.state.get_image_area() {
+            let id = visible_graphic(&app).map(|graphic| graphic.id);
+            placer.retain(terminal.backend_mut(), id, area)?;
+        }
+
         terminal.draw(|rect| ui::draw(rect, &mut app))?;
 
+        if let Some(area) = app.state.get_image_area() {
+            placer.update(terminal.backend_mut(), visible_graphic(&app), area)?;
+        }
+
         let result = match events.next().await {
             InputEvent::Input(key) => app.do_action(key).await,
             InputEvent::Tick => app.update_on_tick().await,
@@ -49,8 +72,15 @@ pub async fn start_ui<'a>(app: &Arc<tokio::sync::Mutex<App<'a>>>, path: String)
             events.close();
             break;
         }
+
+        events.set_tick_rate(if app.state.is_animating() {
+            animation_tick_rate
+        } else {
+            tick_rate
+        });
     }
 
+    placer.clear(terminal.backend_mut())?;
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
@@ -61,3 +91,11 @@ pub async fn start_ui<'a>(app: &Arc<tokio::sync::Mutex<App<'a>>>, path: String)
 
     Ok(())
 }
+
+fn visible_graphic<'a>(app: &'a App) -> Option<&'a Graphic> {
+    if app.is_loading() || app.is_help_shown() {
+        None
+    } else {
+        app.state.get_current_graphic()
+    }
+}
@@ -1,13 +1,25 @@
+use crossterm::tty::IsTty;
 use eyre::Result;
 use picterm::{
-    app::App,
-    image::print_term_image,
+    adjust::Adjustments,
+    app::{App, AppConfig},
+    config::Config,
+    decoder::{self, external},
+    graphics::{self, Protocol},
+    image::{print_area, print_term_image, to_display_image},
     io::{handler::IoAsyncHandler, IoEvent},
+    renderer::{
+        color::ColorDepth,
+        transparency::{self, Backdrop},
+        RenderOptions, RendererKind,
+    },
     start_ui,
-    utils::{select_mode, ImageMode, RunMode},
+    tone_map::ToneMap,
+    transform::Transform,
+    utils::{select_mode, FileFilter, ImageMode, RunMode},
 };
 use seahorse::{App as SeahorseApp, Context, Flag, FlagType};
-use std::{env, sync::Arc};
+use std::{env, io::stdout, path::Path, str::FromStr, sync::Arc};
 
 fn main() -> Result<()> {
     let args = env::args().collect();
@@ -24,6 +36,71 @@ fn main() -> Result<()> {
                 .alias("g")
                 .description("Gray scale mode"),
         )
+        .flag(
+            Flag::new("renderer", FlagType::String)
+                .alias("r")
+                .description(
+                    "Renderer (auto, half-block, full-cell, braille, braille:<cutoff>, \
+                     braille-dither, ascii, ascii-color, kitty, sixel, iterm2)",
+                ),
+        )
+        .flag(
+            Flag::new("ramp", FlagType::String)
+                .description("Characters used by the ascii renderer, from dark to bright"),
+        )
+        .flag(
+            Flag::new("colors", FlagType::String)
+                .description("Colors of the terminal (16, 256, truecolor), detected by default"),
+        )
+        .flag(
+            Flag::new("dither", FlagType::String)
+                .description("Dithering with 16 or 256 colors (floyd-steinberg, ordered, none)"),
+        )
+        .flag(
+            Flag::new("fit", FlagType::String)
+                .description("Fit mode (contain, cover, stretch, original, integer)"),
+        )
+        .flag(
+            Flag::new("filter", FlagType::String).description(
+                "Resampling filter (nearest, triangle, catmull-rom, gaussian, lanczos3)",
+            ),
+        )
+        .flag(
+            Flag::new("backdrop", FlagType::String)
+                .description("Backdrop of transparent pixels (terminal, checkerboard, color)"),
+        )
+        .flag(
+            Flag::new("backdrop-color", FlagType::String)
+                .description("Color of the solid backdrop, as #rrggbb"),
+        )
+        .flag(
+            Flag::new("tone-map", FlagType::String)
+                .description("Tone mapping of HDR and OpenEXR images (clamp, reinhard, filmic)"),
+        )
+        .flag(
+            Flag::new("exposure", FlagType::Float)
+                .description("Exposure compensation of HDR and OpenEXR images, in stops"),
+        )
+        .flag(
+            Flag::new("rotate", FlagType::Int)
+                .description("Rotate the image clockwise by a multiple of 90 degrees"),
+        )
+        .flag(
+            Flag::new("flip", FlagType::String)
+                .description("Flip the image after rotating it (horizontal, vertical)"),
+        )
+        .flag(Flag::new("config", FlagType::String).description(
+            "Config file, $XDG_CONFIG_HOME/picterm/config.toml or \
+                 ~/.config/picterm/config.toml by default",
+        ))
+        .flag(
+            Flag::new("include", FlagType::String)
+                .description("Only list files with these extensions (comma separated)"),
+        )
+        .flag(
+            Flag::new("exclude", FlagType::String)
+                .description("Do not list files with these extensions (comma separated)"),
+        )
         .action(action);
 
     cli_app.run(args);
@@ -32,34 +109,83 @@ fn main() -> Result<()> {
 }
 
 fn action(c: &Context) {
+    load_config(c);
+
     match select_mode(&c.args) {
         RunMode::CLI => cli_main(c),
         RunMode::TUI => tui_main(c),
     }
 }
 
+/// Reads the config and registers its external decoders.
+fn load_config(c: &Context) {
+    let config = match c.string_flag("config") {
+        Ok(path) if !Path::new(&path).is_file() => Err(format!("{}: no such file", path)),
+        Ok(path) => Config::load(Path::new(&path)),
+        Err(_) => Config::default_path().map_or(Ok(Config::default()), |path| Config::load(&path)),
+    };
+
+    match config.and_then(|config| config.external_decoders()) {
+        Ok(decoders) => external::register(decoders),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
 fn cli_main(c: &Context) {
-    let img = image::open(&c.args[0]).unwrap();
+    let (renderer, _) = renderer(c, false);
+    let options = render_options(c);
+    let renderer = renderer.renderer(&options);
+
+    let (cols, rows) = print_area();
+    let mut decoded = match decoder::open(Path::new(&c.args[0]), renderer.resolution(cols, rows)) {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    // Animations are printed as their first frame.
+    let frame = decoded.frames.swap_remove(0);
+    let img = to_display_image(&frame.pixels, tone_map(c));
+    let img = decoded.orientation.then(transform(c)).apply(img);
     let mode = if c.bool_flag("gray") {
         ImageMode::GrayScale
     } else {
         ImageMode::Rgba
     };
 
-    print_term_image(img, mode);
+    print_term_image(img, mode, renderer.as_ref(), options.colors.depth);
 }
 
 fn tui_main(c: &Context) {
     let rt = tokio::runtime::Runtime::new().unwrap();
     rt.block_on(async {
-        let (sync_io_tx, mut sync_io_rx) = tokio::sync::mpsc::channel::<IoEvent>(1000);
+        let (sync_io_tx, sync_io_rx) = tokio::sync::mpsc::channel::<IoEvent>(1000);
         let mode = if c.bool_flag("gray") {
             ImageMode::GrayScale
         } else {
             ImageMode::Rgba
         };
 
-        let app = Arc::new(tokio::sync::Mutex::new(App::new(sync_io_tx.clone(), mode)));
+        let (renderer, protocol) = renderer(c, true);
+        let config = AppConfig {
+            image_mode: mode,
+            renderer,
+            render_options: render_options(c),
+            protocol,
+            file_filter: file_filter(c),
+            tone_map: tone_map(c),
+            transform: transform(c),
+            adjustments: Adjustments::default(),
+        };
+
+        let app = Arc::new(tokio::sync::Mutex::new(App::new(
+            sync_io_tx.clone(),
+            config,
+        )));
         let app_ui = Arc::clone(&app);
 
         let path = match c.args.len() {
@@ -69,11 +195,124 @@ fn tui_main(c: &Context) {
 
         tokio::spawn(async move {
             let mut handler = IoAsyncHandler::new(app);
-            while let Some(io_event) = sync_io_rx.recv().await {
-                handler.handle_io_event(io_event).await;
-            }
+            handler.run(sync_io_rx).await;
         });
 
         start_ui(&app_ui, path).await.unwrap();
     });
 }
+
+/// Returns the renderer selected with `--renderer` and the graphics protocol
+/// it can switch to at runtime. The terminal is only probed for the protocol
+/// when the renderer is `auto` or when it is `switchable`.
+fn renderer(c: &Context, switchable: bool) -> (RendererKind, Option<Protocol>) {
+    let name = c
+        .string_flag("renderer")
+        .unwrap_or_else(|_| "auto".to_string());
+    if name == "auto" {
+        // Escape sequences are garbage in logs and unwanted with NO_COLOR.
+        if env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) || !stdout().is_tty() {
+            return (RendererKind::Ascii { colored: false }, None);
+        }
+
+        let protocol = graphics::detect();
+        let renderer = protocol.map_or(RendererKind::HalfBlock, RendererKind::Graphics);
+        return (renderer, protocol);
+    }
+
+    match parse_or_exit(&name) {
+        RendererKind::Graphics(protocol) => (RendererKind::Graphics(protocol), Some(protocol)),
+        renderer if switchable => (renderer, graphics::detect()),
+        renderer => (renderer, None),
+    }
+}
+
+fn render_options(c: &Context) -> RenderOptions {
+    let mut options = RenderOptions::default();
+
+    if let Ok(ramp) = c.string_flag("ramp") {
+        if ramp.is_empty() {
+            eprintln!("The ramp must contain at least one character.");
+            std::process::exit(1);
+        }
+        options.ramp = ramp.chars().collect();
+    }
+
+    options.colors.depth = match c.string_flag("colors") {
+        Ok(depth) => parse_or_exit(&depth),
+        Err(_) => ColorDepth::detect(),
+    };
+    if let Ok(dither) = c.string_flag("dither") {
+        options.colors.dither = parse_or_exit(&dither);
+    }
+    if let Ok(fit) = c.string_flag("fit") {
+        options.scaling.fit = parse_or_exit(&fit);
+    }
+    if let Ok(filter) = c.string_flag("filter") {
+        options.scaling.filter = parse_or_exit(&filter);
+    }
+
+    let transparency = &mut options.transparency;
+    if let Ok(color) = c.string_flag("backdrop-color") {
+        transparency.color = transparency::parse_color(&color).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+        transparency.backdrop = Backdrop::Color;
+    }
+    if let Ok(backdrop) = c.string_flag("backdrop") {
+        transparency.backdrop = parse_or_exit(&backdrop);
+    }
+    // The TUI can switch to it later, but not query it once started.
+    transparency.detect_background();
+
+    options
+}
+
+fn file_filter(c: &Context) -> FileFilter {
+    let extensions = |name| {
+        c.string_flag(name)
+            .map(|s| FileFilter::parse_extensions(&s))
+            .unwrap_or_default()
+    };
+
+    FileFilter {
+        include: extensions("include"),
+        exclude: extensions("exclude"),
+    }
+}
+
+fn tone_map(c: &Context) -> ToneMap {
+    let mut tone_map = ToneMap::default();
+
+    if let Ok(operator) = c.string_flag("tone-map") {
+        tone_map.operator = parse_or_exit(&operator);
+    }
+    if let Ok(exposure) = c.float_flag("exposure") {
+        tone_map.exposure = exposure as f32;
+    }
+
+    tone_map
+}
+
+fn transform(c: &Context) -> Transform {
+    let mut transform = match c.int_flag("rotate") {
+        Ok(degrees) => Transform::rotation(degrees as i64).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }),
+        Err(_) => Transform::default(),
+    };
+    if let Ok(axis) = c.string_flag("flip") {
+        transform = transform.flip(parse_or_exit(&axis));
+    }
+
+    transform
+}
+
+fn parse_or_exit<T: FromStr<Err = String>>(s: &str) -> T {
+    s.parse().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
@@ -0,0 +1,111 @@
+use super::{Cell, Rendered, Renderer, Scaling, Transparency};
+use image::{buffer::ConvertBuffer, DynamicImage, GrayAlphaImage, LumaA, Rgba};
+use rgb::RGB8;
+
+pub const DEFAULT_RAMP: &str = " .:-=+*#%@";
+
+/// Picks a character from a density ramp by luminance, optionally colored
+/// with the pixel color.
+pub struct Ascii {
+    /// Characters ordered from the darkest to the brightest, never empty.
+    pub ramp: Vec<char>,
+    pub colored: bool,
+    pub scaling: Scaling,
+    pub transparency: Transparency,
+}
+
+impl Renderer for Ascii {
+    fn render(&self, img: &DynamicImage, cols: u32, rows: u32) -> Rendered {
+        let (imgbuf, w, h) = self.scaling.fit_cells(img, cols, rows, (1, 1));
+        let mut rgbabuf = imgbuf.to_rgba8();
+        self.transparency.composite(&mut rgbabuf, (2, 1));
+        let lumabuf: GrayAlphaImage = rgbabuf.convert();
+
+        let cells = (0..h)
+            .map(|y| {
+                (0..w)
+                    .map(|x| {
+                        let LumaA([luma, alpha]) = *lumabuf.get_pixel(x, y);
+                        if alpha == 0 {
+                            return Cell::BLANK;
+                        }
+
+                        let symbol = self.symbol(luma);
+                        if self.colored {
+                            let Rgba(data) = *rgbabuf.get_pixel(x, y);
+                            Cell::new(symbol, Some(RGB8::new(data[0], data[1], data[2])), None)
+                        } else {
+                            Cell::new(symbol, None, None)
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Rendered::Cells(cells)
+    }
+}
+
+impl Ascii {
+    fn symbol(&self, luma: u8) -> char {
+        let last = self.ramp.len() - 1;
+        self.ramp[(luma as usize * last + 127) / 255]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::scaling::Filter;
+    use image::RgbaImage;
+
+    fn ascii(colored: bool) -> Ascii {
+        Ascii {
+            ramp: ".oO@".chars().collect(),
+            colored,
+            scaling: Scaling {
+                filter: Filter::Nearest,
+                ..Scaling::default()
+            },
+            transparency: Transparency::default(),
+        }
+    }
+
+    #[test]
+    fn picks_the_ends_of_the_ramp() {
+        let ascii = ascii(false);
+        assert_eq!(ascii.symbol(0), '.');
+        assert_eq!(ascii.symbol(255), '@');
+        assert_eq!(ascii.symbol(128), 'O');
+    }
+
+    #[test]
+    fn renders_without_colors() {
+        let img = RgbaImage::from_fn(3, 2, |x, _| match x {
+            0 => Rgba([0, 0, 0, 255]),
+            1 => Rgba([255, 255, 255, 255]),
+            _ => Rgba([255, 255, 255, 0]),
+        });
+        let img = DynamicImage::ImageRgba8(img);
+
+        let Rendered::Cells(cells) = ascii(false).render(&img, 10, 10) else {
+            panic!("expected cells");
+        };
+        assert_eq!(
+            cells,
+            [[
+                Cell::new('.', None, None),
+                Cell::new('@', None, None),
+                Cell::BLANK
+            ]]
+        );
+
+        let Rendered::Cells(cells) = ascii(true).render(&img, 10, 10) else {
+            panic!("expected cells");
+        };
+        assert_eq!(
+            cells[0][1],
+            Cell::new('@', Some(RGB8::new(255, 255, 255)), None)
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use super::{bayer, Cell, Rendered, Renderer, Scaling, Transparency};
+use image::{DynamicImage, Rgba, RgbaImage};
+use rgb::RGB8;
+
+/// First braille pattern, with no dots raised.
+const BRAILLE_BLANK: u32 = 0x2800;
+
+/// Bit of each dot in a braille pattern, indexed by `[y][x]`.
+const DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+pub const DEFAULT_CUTOFF: u8 = 128;
+
+/// How pixels are turned into raised or flat dots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Threshold {
+    /// Raise dots brighter than the cutoff.
+    Fixed(u8),
+    /// Compare against a Bayer matrix to keep gradients visible.
+    Ordered,
+}
+
+impl Threshold {
+    fn is_raised(&self, luma: u8, x: u32, y: u32) -> bool {
+        match self {
+            Threshold::Fixed(cutoff) => luma > *cutoff,
+            Threshold::Ordered => luma > bayer(x, y) * 16 + 8,
+        }
+    }
+}
+
+/// Maps 2x4 pixel blocks to braille characters colored with the average of
+/// their raised dots.
+pub struct Braille {
+    pub threshold: Threshold,
+    pub scaling: Scaling,
+    pub transparency: Transparency,
+}
+
+impl Renderer for Braille {
+    fn render(&self, img: &DynamicImage, cols: u32, rows: u32) -> Rendered {
+        let (imgbuf, w, h) = self.scaling.fit_cells(img, cols, rows, (2, 4));
+        let mut imgbuf = imgbuf.to_rgba8();
+        self.transparency.composite(&mut imgbuf, (4, 4));
+
+        let cells = (0..h)
+            .map(|row| (0..w).map(|col| self.cell(&imgbuf, col, row)).collect())
+            .collect();
+
+        Rendered::Cells(cells)
+    }
+
+    fn resolution(&self, cols: u32, rows: u32) -> (u32, u32) {
+        (2 * cols, 4 * rows)
+    }
+}
+
+impl Braille {
+    fn cell(&self, imgbuf: &RgbaImage, col: u32, row: u32) -> Cell {
+        let mut pattern = 0;
+        let mut sum = [0u32; 3];
+        let mut raised = 0;
+
+        for (dy, dots) in DOTS.iter().enumerate() {
+            for (dx, dot) in dots.iter().enumerate() {
+                let (x, y) = (2 * col + dx as u32, 4 * row + dy as u32);
+                let Rgba(data) = *imgbuf.get_pixel(x, y);
+
+                if data[3] != 0 && self.threshold.is_raised(luma(data), x, y) {
+                    pattern |= dot;
+                    sum.iter_mut().zip(data).for_each(|(s, c)| *s += c as u32);
+                    raised += 1;
+                }
+            }
+        }
+
+        if raised == 0 {
+            return Cell::BLANK;
+        }
+
+        let symbol = char::from_u32(BRAILLE_BLANK + pattern as u32).unwrap();
+        let fg = RGB8::new(
+            (sum[0] / raised) as u8,
+            (sum[1] / raised) as u8,
+            (sum[2] / raised) as u8,
+        );
+        Cell::new(symbol, Some(fg), None)
+    }
+}
+
+fn luma(data: [u8; 4]) -> u8 {
+    ((data[0] as u32 * 299 + data[1] as u32 * 587 + data[2] as u32 * 114) / 1000) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn braille(threshold: Threshold) -> Braille {
+        Braille {
+            threshold,
+            scaling: Scaling::default(),
+            transparency: Transparency::default(),
+        }
+    }
+
+    #[test]
+    fn raises_dots_above_the_cutoff() {
+        // Dots 1, 5 and 8 bright, the others dark.
+        let img = RgbaImage::from_fn(2, 4, |x, y| match (x, y) {
+            (0, 0) => Rgba([250, 150, 50, 255]),
+            (1, 1) => Rgba([250, 250, 250, 255]),
+            (1, 3) => Rgba([150, 250, 200, 255]),
+            _ => Rgba([100, 100, 100, 255]),
+        });
+
+        let cell = braille(Threshold::Fixed(DEFAULT_CUTOFF)).cell(&img, 0, 0);
+        assert_eq!(cell.symbol, '\u{2891}');
+        assert_eq!(cell.fg, Some(RGB8::new(216, 216, 166)));
+        assert_eq!(cell.bg, None);
+
+        let cell = braille(Threshold::Fixed(255)).cell(&img, 0, 0);
+        assert_eq!(cell, Cell::BLANK);
+    }
+
+    #[test]
+    fn raises_dots_following_the_bayer_matrix() {
+        // Mid gray raises the dots whose matrix value is below half.
+        let img = RgbaImage::from_pixel(2, 4, Rgba([128, 128, 128, 255]));
+        let cell = braille(Threshold::Ordered).cell(&img, 0, 0);
+        assert_eq!(cell.symbol, '\u{2895}');
+        assert_eq!(cell.fg, Some(RGB8::new(128, 128, 128)));
+    }
+
+    #[test]
+    fn leaves_transparent_pixels_blank() {
+        let img = RgbaImage::from_pixel(1, 2, Rgba([255, 255, 255, 0]));
+        let Rendered::Cells(cells) =
+            braille(Threshold::Fixed(0)).render(&DynamicImage::ImageRgba8(img), 10, 10)
+        else {
+            panic!("expected cells");
+        };
+        assert_eq!(cells, [[Cell::BLANK]]);
+    }
+}
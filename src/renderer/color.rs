@@ -0,0 +1,393 @@
+use super::bayer;
+use ansi_rgb::{Canvas, Color3, Color4, Color8, FormatColor};
+use image::RgbaImage;
+use rgb::RGB8;
+use std::{env, fmt, str::FromStr, sync::OnceLock};
+use tui::style::Color;
+
+/// The standard xterm values of the 16 ANSI colors.
+const ANSI16: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Terminals that display true color without setting `COLORTERM`, with their
+/// variants such as `foot-extra`.
+const TRUECOLOR_TERMS: [&str; 4] = ["xterm-kitty", "wezterm", "alacritty", "foot"];
+
+/// Channel levels of the xterm 6x6x6 color cube.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Number of colors the terminal can display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorDepth {
+    #[default]
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+/// How colors missing from the palette are approximated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dither {
+    None,
+    #[default]
+    FloydSteinberg,
+    Ordered,
+}
+
+/// A color ready to be printed with `ansi_rgb`.
+#[derive(Debug, Clone, Copy)]
+pub enum AnsiColor {
+    Rgb(RGB8),
+    Color8(Color8),
+    Color4(Color4),
+}
+
+impl FormatColor for AnsiColor {
+    fn prelude(&self, f: &mut fmt::Formatter, canvas: Canvas) -> fmt::Result {
+        match self {
+            AnsiColor::Rgb(color) => color.prelude(f, canvas),
+            AnsiColor::Color8(color) => color.prelude(f, canvas),
+            AnsiColor::Color4(color) => color.prelude(f, canvas),
+        }
+    }
+}
+
+impl ColorDepth {
+    /// Guesses the color depth from `COLORTERM` and `TERM`.
+    pub fn detect() -> Self {
+        Self::from_env(
+            env::var("COLORTERM").ok().as_deref(),
+            env::var("TERM").ok().as_deref(),
+        )
+    }
+
+    /// Only the Linux console, `screen` and unknown terminals get 16 colors.
+    fn from_env(colorterm: Option<&str>, term: Option<&str>) -> Self {
+        let term = term.unwrap_or_default();
+        let truecolor_term = TRUECOLOR_TERMS.iter().any(|name| {
+            term.strip_prefix(name)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+        });
+
+        if matches!(colorterm, Some("truecolor" | "24bit"))
+            || term.ends_with("-direct")
+            || term.ends_with("truecolor")
+            || truecolor_term
+        {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") || term.starts_with("xterm") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+
+    /// Returns the palette and the index of its first entry, or `None` for true color.
+    fn palette(&self) -> Option<(&'static [RGB8], u8)> {
+        static ANSI256_PALETTE: OnceLock<Vec<RGB8>> = OnceLock::new();
+        static ANSI16_PALETTE: OnceLock<Vec<RGB8>> = OnceLock::new();
+
+        match self {
+            ColorDepth::TrueColor => None,
+            // The 16 system colors are skipped because terminals customize them.
+            ColorDepth::Ansi256 => Some((ANSI256_PALETTE.get_or_init(ansi256_palette), 16)),
+            ColorDepth::Ansi16 => Some((
+                ANSI16_PALETTE
+                    .get_or_init(|| ANSI16.iter().map(|&(r, g, b)| RGB8::new(r, g, b)).collect()),
+                0,
+            )),
+        }
+    }
+
+    /// Returns the palette index closest to `color`, or `None` for true color.
+    pub fn index_of(&self, color: RGB8) -> Option<u8> {
+        let (palette, offset) = self.palette()?;
+        let (i, _) = palette
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, &entry)| distance(entry, color))
+            .unwrap();
+        Some(offset + i as u8)
+    }
+
+    /// Returns the displayable color closest to `color`.
+    pub fn nearest(&self, color: RGB8) -> RGB8 {
+        match (self.palette(), self.index_of(color)) {
+            (Some((palette, offset)), Some(i)) => palette[(i - offset) as usize],
+            _ => color,
+        }
+    }
+
+    pub fn tui_color(&self, color: RGB8) -> Color {
+        match self.index_of(color) {
+            None => Color::Rgb(color.r, color.g, color.b),
+            Some(i) if *self == ColorDepth::Ansi256 => Color::Indexed(i),
+            Some(i) => [
+                Color::Black,
+                Color::Red,
+                Color::Green,
+                Color::Yellow,
+                Color::Blue,
+                Color::Magenta,
+                Color::Cyan,
+                Color::Gray,
+                Color::DarkGray,
+                Color::LightRed,
+                Color::LightGreen,
+                Color::LightYellow,
+                Color::LightBlue,
+                Color::LightMagenta,
+                Color::LightCyan,
+                Color::White,
+            ][i as usize],
+        }
+    }
+
+    pub fn ansi_color(&self, color: RGB8) -> AnsiColor {
+        match self.index_of(color) {
+            None => AnsiColor::Rgb(color),
+            Some(i) if *self == ColorDepth::Ansi256 => AnsiColor::Color8(Color8::new(i)),
+            Some(i) => {
+                let color3 = [
+                    Color3::BLACK,
+                    Color3::RED,
+                    Color3::GREEN,
+                    Color3::YELLOW,
+                    Color3::BLUE,
+                    Color3::MAGENTA,
+                    Color3::CYAN,
+                    Color3::WHITE,
+                ][(i % 8) as usize];
+                AnsiColor::Color4(Color4::new(color3, i >= 8))
+            }
+        }
+    }
+}
+
+impl FromStr for ColorDepth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "truecolor" | "24bit" => Ok(ColorDepth::TrueColor),
+            "256" => Ok(ColorDepth::Ansi256),
+            "16" => Ok(ColorDepth::Ansi16),
+            _ => Err(format!("Unknown color depth: {}", s)),
+        }
+    }
+}
+
+impl FromStr for Dither {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Dither::None),
+            "floyd-steinberg" => Ok(Dither::FloydSteinberg),
+            "ordered" => Ok(Dither::Ordered),
+            _ => Err(format!("Unknown dithering: {}", s)),
+        }
+    }
+}
+
+/// Snaps the opaque pixels of `img` to colors `depth` can display.
+pub fn dither(img: &mut RgbaImage, depth: ColorDepth, dither: Dither) {
+    if depth == ColorDepth::TrueColor {
+        return;
+    }
+
+    match dither {
+        Dither::None => {
+            for pixel in img.pixels_mut().filter(|p| p[3] != 0) {
+                let color = depth.nearest(RGB8::new(pixel[0], pixel[1], pixel[2]));
+                pixel.0 = [color.r, color.g, color.b, pixel[3]];
+            }
+        }
+        Dither::Ordered => {
+            // Palette entries are roughly 40 apart in 256 colors and much further in 16.
+            let spread = if depth == ColorDepth::Ansi256 {
+                48
+            } else {
+                128
+            };
+            for (x, y, pixel) in img.enumerate_pixels_mut().filter(|(_, _, p)| p[3] != 0) {
+                let offset = (bayer(x, y) as i16 * 2 - 15) * spread / 32;
+                let [r, g, b] = [0, 1, 2].map(|c| clamp(pixel[c] as i16 + offset));
+                let color = depth.nearest(RGB8::new(r, g, b));
+                pixel.0 = [color.r, color.g, color.b, pixel[3]];
+            }
+        }
+        Dither::FloydSteinberg => floyd_steinberg(img, depth),
+    }
+}
+
+fn floyd_steinberg(img: &mut RgbaImage, depth: ColorDepth) {
+    let (width, height) = img.dimensions();
+    let mut errors = vec![[0i16; 3]; (width * height) as usize];
+
+    for y in 0..height {
+        for x in 0..width {
+            let pixel = img.get_pixel_mut(x, y);
+            if pixel[3] == 0 {
+                continue;
+            }
+
+            let error = errors[(y * width + x) as usize];
+            let [r, g, b] = [0, 1, 2].map(|c| clamp(pixel[c] as i16 + error[c]));
+            let color = depth.nearest(RGB8::new(r, g, b));
+            pixel.0 = [color.r, color.g, color.b, pixel[3]];
+
+            let diff = [
+                r as i16 - color.r as i16,
+                g as i16 - color.g as i16,
+                b as i16 - color.b as i16,
+            ];
+            let neighbors = [(1, 0, 7), (-1, 1, 3), (0, 1, 5), (1, 1, 1)];
+            for (dx, dy, weight) in neighbors {
+                let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+                if nx < 0 || nx >= width as i64 || ny >= height as i64 {
+                    continue;
+                }
+                let error = &mut errors[(ny as u32 * width + nx as u32) as usize];
+                for c in 0..3 {
+                    error[c] += diff[c] * weight / 16;
+                }
+            }
+        }
+    }
+}
+
+fn ansi256_palette() -> Vec<RGB8> {
+    let cube = (0..216).map(|i| {
+        RGB8::new(
+            CUBE_LEVELS[i / 36],
+            CUBE_LEVELS[i / 6 % 6],
+            CUBE_LEVELS[i % 6],
+        )
+    });
+    let grays = (0..24).map(|i| {
+        let level = 8 + i * 10;
+        RGB8::new(level, level, level)
+    });
+
+    cube.chain(grays).collect()
+}
+
+fn distance(a: RGB8, b: RGB8) -> u32 {
+    let dr = a.r as i32 - b.r as i32;
+    let dg = a.g as i32 - b.g as i32;
+    let db = a.b as i32 - b.b as i32;
+    (2 * dr * dr + 4 * dg * dg + 3 * db * db) as u32
+}
+
+fn clamp(value: i16) -> u8 {
+    value.clamp(0, 255) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn grays(img: &RgbaImage) -> Vec<u8> {
+        img.pixels().map(|p| p[0]).collect()
+    }
+
+    #[test]
+    fn detects_the_depth_from_the_environment() {
+        let depth = |colorterm, term| ColorDepth::from_env(colorterm, Some(term));
+        assert_eq!(depth(Some("truecolor"), "screen"), ColorDepth::TrueColor);
+        assert_eq!(depth(Some("24bit"), "linux"), ColorDepth::TrueColor);
+        for term in [
+            "xterm-direct",
+            "tmux-direct",
+            "xterm-truecolor",
+            "xterm-kitty",
+            "wezterm",
+            "alacritty",
+            "foot",
+            "foot-extra",
+        ] {
+            assert_eq!(depth(None, term), ColorDepth::TrueColor, "{}", term);
+        }
+
+        for term in [
+            "xterm",
+            "xterm-256color",
+            "screen-256color",
+            "tmux-256color",
+        ] {
+            assert_eq!(depth(None, term), ColorDepth::Ansi256, "{}", term);
+        }
+        assert_eq!(depth(Some("yes"), "xterm-color"), ColorDepth::Ansi256);
+
+        for term in ["linux", "screen", "vt100", "footloose", "dumb"] {
+            assert_eq!(depth(None, term), ColorDepth::Ansi16, "{}", term);
+        }
+        assert_eq!(ColorDepth::from_env(None, None), ColorDepth::Ansi16);
+    }
+
+    #[test]
+    fn finds_the_nearest_palette_index() {
+        let red = RGB8::new(250, 5, 5);
+        assert_eq!(ColorDepth::TrueColor.index_of(red), None);
+        assert_eq!(
+            ColorDepth::Ansi256.index_of(RGB8::new(255, 0, 0)),
+            Some(196)
+        );
+        assert_eq!(
+            ColorDepth::Ansi256.index_of(RGB8::new(128, 128, 128)),
+            Some(244)
+        );
+        assert_eq!(ColorDepth::Ansi16.index_of(red), Some(9));
+        assert_eq!(ColorDepth::Ansi16.index_of(RGB8::new(0, 0, 0)), Some(0));
+        assert_eq!(ColorDepth::Ansi16.nearest(red), RGB8::new(255, 0, 0));
+    }
+
+    #[test]
+    fn snaps_without_dithering() {
+        let mut img = RgbaImage::from_pixel(2, 1, Rgba([250, 5, 5, 255]));
+        img.put_pixel(1, 0, Rgba([250, 5, 5, 0]));
+        dither(&mut img, ColorDepth::Ansi16, Dither::None);
+        assert_eq!(img.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        // Transparent pixels are left alone.
+        assert_eq!(img.get_pixel(1, 0).0, [250, 5, 5, 0]);
+    }
+
+    #[test]
+    fn diffuses_the_error() {
+        let mut img = RgbaImage::from_pixel(4, 1, Rgba([64, 64, 64, 255]));
+        dither(&mut img, ColorDepth::Ansi16, Dither::FloydSteinberg);
+        assert_eq!(grays(&img), [127, 0, 127, 0]);
+    }
+
+    #[test]
+    fn dithers_with_the_bayer_matrix() {
+        let mut img = RgbaImage::from_pixel(4, 1, Rgba([64, 64, 64, 255]));
+        dither(&mut img, ColorDepth::Ansi16, Dither::Ordered);
+        assert_eq!(grays(&img), [0, 127, 0, 127]);
+    }
+
+    #[test]
+    fn keeps_true_color() {
+        let mut img = RgbaImage::from_pixel(2, 2, Rgba([64, 65, 66, 255]));
+        dither(&mut img, ColorDepth::TrueColor, Dither::FloydSteinberg);
+        assert!(img.pixels().all(|p| p.0 == [64, 65, 66, 255]));
+    }
+}
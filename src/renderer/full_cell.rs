@@ -0,0 +1,76 @@
+use super::{color, Cell, Colors, Rendered, Renderer, Scaling, Transparency};
+use image::{DynamicImage, Rgba};
+use rgb::RGB8;
+
+/// Paints one pixel per cell with a colored background.
+pub struct FullCell {
+    pub colors: Colors,
+    pub scaling: Scaling,
+    pub transparency: Transparency,
+}
+
+impl Renderer for FullCell {
+    fn render(&self, img: &DynamicImage, cols: u32, rows: u32) -> Rendered {
+        let (img, _, _) = self.scaling.fit_cells(img, cols, rows, (1, 1));
+        let mut imgbuf = img.to_rgba8();
+        self.transparency.composite(&mut imgbuf, (2, 1));
+        color::dither(&mut imgbuf, self.colors.depth, self.colors.dither);
+        let (width, height) = imgbuf.dimensions();
+
+        let cells = (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| {
+                        let Rgba(data) = *imgbuf.get_pixel(x, y);
+
+                        if data[3] == 0 {
+                            Cell::BLANK
+                        } else {
+                            Cell::new(' ', None, Some(RGB8::new(data[0], data[1], data[2])))
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Rendered::Cells(cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::scaling::Filter;
+    use image::RgbaImage;
+
+    #[test]
+    fn paints_the_background() {
+        // Cells are twice as tall as wide, so pairs of rows become one.
+        let img = RgbaImage::from_fn(2, 4, |x, y| match (x, y / 2) {
+            (0, 0) => Rgba([10, 20, 30, 255]),
+            (1, 0) => Rgba([0, 0, 0, 0]),
+            _ => Rgba([200, 100, 50, 255]),
+        });
+
+        let renderer = FullCell {
+            colors: Colors::default(),
+            scaling: Scaling {
+                filter: Filter::Nearest,
+                ..Scaling::default()
+            },
+            transparency: Transparency::default(),
+        };
+        let Rendered::Cells(cells) = renderer.render(&DynamicImage::ImageRgba8(img), 10, 10) else {
+            panic!("expected cells");
+        };
+
+        let bg = |r, g, b| Cell::new(' ', None, Some(RGB8::new(r, g, b)));
+        assert_eq!(
+            cells,
+            [
+                [bg(10, 20, 30), Cell::BLANK],
+                [bg(200, 100, 50), bg(200, 100, 50)],
+            ]
+        );
+    }
+}
@@ -0,0 +1,31 @@
+use super::{Rendered, Renderer, Scaling, Transparency};
+use crate::graphics::{self, Protocol};
+use image::DynamicImage;
+
+/// Transmits the pixels with a terminal graphics protocol.
+pub struct Graphics {
+    pub protocol: Protocol,
+    pub scaling: Scaling,
+    pub transparency: Transparency,
+}
+
+impl Renderer for Graphics {
+    fn render(&self, img: &DynamicImage, cols: u32, rows: u32) -> Rendered {
+        let cell = graphics::cell_size();
+        let (img, cols, rows) = self.scaling.fit_pixels(img, cols, rows, cell);
+        let mut img = img.to_rgba8();
+        self.transparency.composite(&mut img, (cell.0, cell.0));
+
+        Rendered::Pixels {
+            protocol: self.protocol,
+            img,
+            cols,
+            rows,
+        }
+    }
+
+    fn resolution(&self, cols: u32, rows: u32) -> (u32, u32) {
+        let (width, height) = graphics::cell_size();
+        (cols * width, rows * height)
+    }
+}
@@ -0,0 +1,93 @@
+use super::{color, Cell, Colors, Rendered, Renderer, Scaling, Transparency};
+use image::{DynamicImage, Rgba};
+use rgb::RGB8;
+
+pub const UPPER_HALF_BLOCK: char = '▀';
+pub const LOWER_HALF_BLOCK: char = '▄';
+
+/// Paints two vertically stacked pixels per cell with the upper half block
+/// glyph, doubling the vertical resolution.
+pub struct HalfBlock {
+    pub colors: Colors,
+    pub scaling: Scaling,
+    pub transparency: Transparency,
+}
+
+impl Renderer for HalfBlock {
+    fn render(&self, img: &DynamicImage, cols: u32, rows: u32) -> Rendered {
+        let (img, _, _) = self.scaling.fit_cells(img, cols, rows, (1, 2));
+        let mut imgbuf = img.to_rgba8();
+        self.transparency.composite(&mut imgbuf, (2, 2));
+        color::dither(&mut imgbuf, self.colors.depth, self.colors.dither);
+        let (width, height) = imgbuf.dimensions();
+
+        let cells = (0..height)
+            .step_by(2)
+            .map(|y| {
+                (0..width)
+                    .map(|x| {
+                        let Rgba(top) = *imgbuf.get_pixel(x, y);
+                        let Rgba(bottom) = *imgbuf.get_pixel(x, y + 1);
+
+                        half_block(
+                            (top[3] != 0).then(|| RGB8::new(top[0], top[1], top[2])),
+                            (bottom[3] != 0).then(|| RGB8::new(bottom[0], bottom[1], bottom[2])),
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Rendered::Cells(cells)
+    }
+}
+
+/// Builds a cell holding two vertically stacked pixels.
+///
+/// `None` means a fully transparent pixel, which is left to the terminal background.
+fn half_block(top: Option<RGB8>, bottom: Option<RGB8>) -> Cell {
+    match (top, bottom) {
+        (Some(top), Some(bottom)) => Cell::new(UPPER_HALF_BLOCK, Some(top), Some(bottom)),
+        (Some(top), None) => Cell::new(UPPER_HALF_BLOCK, Some(top), None),
+        (None, Some(bottom)) => Cell::new(LOWER_HALF_BLOCK, Some(bottom), None),
+        (None, None) => Cell::BLANK,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn stacks_two_pixels_per_cell() {
+        let (red, green, blue) = ([255, 0, 0], [0, 255, 0], [0, 0, 255]);
+        let clear = Rgba([0, 0, 0, 0]);
+        let opaque = |[r, g, b]: [u8; 3]| Rgba([r, g, b, 255]);
+        let mut img = RgbaImage::from_pixel(4, 2, clear);
+        img.put_pixel(0, 0, opaque(red));
+        img.put_pixel(0, 1, opaque(blue));
+        img.put_pixel(1, 1, opaque(green));
+        img.put_pixel(2, 0, opaque(red));
+
+        let renderer = HalfBlock {
+            colors: Colors::default(),
+            scaling: Scaling::default(),
+            transparency: Transparency::default(),
+        };
+        let Rendered::Cells(cells) = renderer.render(&DynamicImage::ImageRgba8(img), 10, 10) else {
+            panic!("expected cells");
+        };
+
+        let rgb = |[r, g, b]: [u8; 3]| Some(RGB8::new(r, g, b));
+        assert_eq!(
+            cells,
+            [[
+                Cell::new(UPPER_HALF_BLOCK, rgb(red), rgb(blue)),
+                Cell::new(LOWER_HALF_BLOCK, rgb(green), None),
+                Cell::new(UPPER_HALF_BLOCK, rgb(red), None),
+                Cell::BLANK,
+            ]]
+        );
+    }
+}
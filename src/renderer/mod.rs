@@ -0,0 +1,261 @@
+pub mod ascii;
+pub mod braille;
+pub mod color;
+pub mod full_cell;
+pub mod graphics;
+pub mod half_block;
+pub mod scaling;
+pub mod transparency;
+
+use self::{
+    ascii::Ascii,
+    braille::{Braille, Threshold},
+    color::{ColorDepth, Dither},
+    full_cell::FullCell,
+    graphics::Graphics,
+    half_block::HalfBlock,
+    scaling::Scaling,
+    transparency::Transparency,
+};
+use crate::graphics::Protocol;
+use ansi_rgb::Colorable;
+use image::{DynamicImage, RgbaImage};
+use rgb::RGB8;
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+use tui::{
+    style::Style,
+    text::{Line, Span},
+};
+
+/// 4x4 Bayer matrix used for ordered dithering.
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Converts an image to something a terminal can display.
+pub trait Renderer {
+    /// Renders `img` so that it fits in `cols`x`rows` cells.
+    fn render(&self, img: &DynamicImage, cols: u32, rows: u32) -> Rendered;
+
+    /// Pixels that `cols`x`rows` cells can show, the size vector images are
+    /// rasterized at. Cells are about twice as tall as wide.
+    fn resolution(&self, cols: u32, rows: u32) -> (u32, u32) {
+        (cols, 2 * rows)
+    }
+}
+
+/// The output of a [`Renderer`].
+#[derive(Debug, Clone)]
+pub enum Rendered {
+    /// Rows of colored characters.
+    Cells(Vec<Vec<Cell>>),
+    /// An image to transmit with a graphics protocol, covering `cols`x`rows` cells.
+    Pixels {
+        protocol: Protocol,
+        img: RgbaImage,
+        cols: u32,
+        rows: u32,
+    },
+}
+
+/// A terminal cell. `None` colors are left to the terminal defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub symbol: char,
+    pub fg: Option<RGB8>,
+    pub bg: Option<RGB8>,
+}
+
+impl Cell {
+    pub const BLANK: Cell = Cell {
+        symbol: ' ',
+        fg: None,
+        bg: None,
+    };
+
+    pub fn new(symbol: char, fg: Option<RGB8>, bg: Option<RGB8>) -> Self {
+        Self { symbol, fg, bg }
+    }
+}
+
+/// Settings shared by the renderers.
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Density ramp of the ASCII renderer, from the darkest to the brightest.
+    pub ramp: Vec<char>,
+    pub colors: Colors,
+    pub scaling: Scaling,
+    pub transparency: Transparency,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            ramp: ascii::DEFAULT_RAMP.chars().collect(),
+            colors: Colors::default(),
+            scaling: Scaling::default(),
+            transparency: Transparency::default(),
+        }
+    }
+}
+
+/// The palette available on the terminal and how to approximate images with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Colors {
+    pub depth: ColorDepth,
+    pub dither: Dither,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RendererKind {
+    FullCell,
+    HalfBlock,
+    Braille(Threshold),
+    Ascii { colored: bool },
+    Graphics(Protocol),
+}
+
+impl RendererKind {
+    pub fn renderer(&self, options: &RenderOptions) -> Box<dyn Renderer + Send> {
+        match self {
+            RendererKind::FullCell => Box::new(FullCell {
+                colors: options.colors,
+                scaling: options.scaling,
+                transparency: options.transparency,
+            }),
+            RendererKind::HalfBlock => Box::new(HalfBlock {
+                colors: options.colors,
+                scaling: options.scaling,
+                transparency: options.transparency,
+            }),
+            RendererKind::Braille(threshold) => Box::new(Braille {
+                threshold: *threshold,
+                scaling: options.scaling,
+                transparency: options.transparency,
+            }),
+            RendererKind::Ascii { colored } => Box::new(Ascii {
+                ramp: options.ramp.clone(),
+                colored: *colored,
+                scaling: options.scaling,
+                transparency: options.transparency,
+            }),
+            RendererKind::Graphics(protocol) => Box::new(Graphics {
+                protocol: *protocol,
+                scaling: options.scaling,
+                transparency: options.transparency,
+            }),
+        }
+    }
+
+    /// Returns the renderer following this one, only offering the graphics
+    /// protocol the terminal supports.
+    pub fn next(&self, protocol: Option<Protocol>) -> Self {
+        let mut kinds = vec![
+            RendererKind::HalfBlock,
+            RendererKind::FullCell,
+            RendererKind::Braille(Threshold::Fixed(braille::DEFAULT_CUTOFF)),
+            RendererKind::Braille(Threshold::Ordered),
+            RendererKind::Ascii { colored: false },
+            RendererKind::Ascii { colored: true },
+        ];
+        kinds.extend(protocol.map(RendererKind::Graphics));
+
+        match kinds.iter().position(|kind| kind == self) {
+            Some(i) => kinds[(i + 1) % kinds.len()],
+            None => kinds[0],
+        }
+    }
+}
+
+impl Display for RendererKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RendererKind::FullCell => write!(f, "full-cell"),
+            RendererKind::HalfBlock => write!(f, "half-block"),
+            RendererKind::Braille(Threshold::Fixed(braille::DEFAULT_CUTOFF)) => {
+                write!(f, "braille")
+            }
+            RendererKind::Braille(Threshold::Fixed(cutoff)) => write!(f, "braille:{}", cutoff),
+            RendererKind::Braille(Threshold::Ordered) => write!(f, "braille-dither"),
+            RendererKind::Ascii { colored: false } => write!(f, "ascii"),
+            RendererKind::Ascii { colored: true } => write!(f, "ascii-color"),
+            RendererKind::Graphics(protocol) => write!(f, "{}", protocol),
+        }
+    }
+}
+
+impl FromStr for RendererKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full-cell" => Ok(RendererKind::FullCell),
+            "half-block" => Ok(RendererKind::HalfBlock),
+            "braille" => Ok(RendererKind::Braille(Threshold::Fixed(
+                braille::DEFAULT_CUTOFF,
+            ))),
+            "braille-dither" => Ok(RendererKind::Braille(Threshold::Ordered)),
+            "ascii" => Ok(RendererKind::Ascii { colored: false }),
+            "ascii-color" => Ok(RendererKind::Ascii { colored: true }),
+            "kitty" => Ok(RendererKind::Graphics(Protocol::Kitty)),
+            "sixel" => Ok(RendererKind::Graphics(Protocol::Sixel)),
+            "iterm2" => Ok(RendererKind::Graphics(Protocol::Iterm2)),
+            _ => match s.strip_prefix("braille:").map(str::parse) {
+                Some(Ok(cutoff)) => Ok(RendererKind::Braille(Threshold::Fixed(cutoff))),
+                _ => Err(format!("Unknown renderer: {}", s)),
+            },
+        }
+    }
+}
+
+/// Converts cells to styled lines for the TUI.
+pub fn to_lines<'a>(cells: &[Vec<Cell>], depth: ColorDepth) -> Vec<Line<'a>> {
+    cells
+        .iter()
+        .map(|row| {
+            let spans: Vec<Span> = row
+                .iter()
+                .map(|cell| {
+                    let mut style = Style::default();
+                    if let Some(fg) = cell.fg {
+                        style = style.fg(depth.tui_color(fg));
+                    }
+                    if let Some(bg) = cell.bg {
+                        style = style.bg(depth.tui_color(bg));
+                    }
+                    Span::styled(cell.symbol.to_string(), style)
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// Converts cells to text with ANSI escape sequences, one line per row.
+pub fn to_ansi(cells: &[Vec<Cell>], depth: ColorDepth) -> String {
+    let mut result = String::new();
+
+    for row in cells {
+        for cell in row {
+            let symbol = cell.symbol;
+            let fg = cell.fg.map(|fg| depth.ansi_color(fg));
+            let bg = cell.bg.map(|bg| depth.ansi_color(bg));
+            let cell = match (fg, bg) {
+                (Some(fg), Some(bg)) => symbol.fg(fg).bg(bg).to_string(),
+                (Some(fg), None) => symbol.fg(fg).to_string(),
+                (None, Some(bg)) => symbol.bg(bg).to_string(),
+                (None, None) => symbol.to_string(),
+            };
+            result.push_str(&cell);
+        }
+        result.push('\n');
+    }
+
+    result
+}
+
+/// Threshold of the Bayer matrix at pixel `(x, y)`, in `0..16`.
+fn bayer(x: u32, y: u32) -> u8 {
+    BAYER[(y % 4) as usize][(x % 4) as usize]
+}
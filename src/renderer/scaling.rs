@@ -0,0 +1,312 @@
+use image::{imageops::FilterType, DynamicImage, GenericImageView};
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// How images are sized to the cells they are rendered in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Scaling {
+    pub fit: Fit,
+    pub filter: Filter,
+}
+
+/// How an image is sized to the panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Fit {
+    /// Shrinks large images to fit, keeping their aspect ratio.
+    #[default]
+    Contain,
+    /// Fills the panel, cropping the overflowing sides.
+    Cover,
+    /// Fills the panel, ignoring the aspect ratio.
+    Stretch,
+    /// One pixel of the image per pixel of the panel, cropped to the panel.
+    Original,
+    /// Enlarges by the largest whole factor that fits, shrinks like `Contain`.
+    Integer,
+}
+
+/// Resampling filter used to resize images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Filter {
+    Nearest,
+    #[default]
+    Triangle,
+    CatmullRom,
+    Gaussian,
+    Lanczos3,
+}
+
+impl Fit {
+    pub fn next(self) -> Self {
+        match self {
+            Fit::Contain => Fit::Cover,
+            Fit::Cover => Fit::Stretch,
+            Fit::Stretch => Fit::Original,
+            Fit::Original => Fit::Integer,
+            Fit::Integer => Fit::Contain,
+        }
+    }
+
+    /// Returns the area of an image of `size` to show, as `(x, y, width,
+    /// height)`, and the size to show it at within `bounds`.
+    fn layout(self, size: (u32, u32), bounds: (u32, u32)) -> ((u32, u32, u32, u32), (u32, u32)) {
+        let (width, height) = (size.0.max(1), size.1.max(1));
+        let (bound_width, bound_height) = (bounds.0.max(1), bounds.1.max(1));
+        let whole = (0, 0, width, height);
+        let scale_x = bound_width as f64 / width as f64;
+        let scale_y = bound_height as f64 / height as f64;
+        let scaled = |scale: f64| {
+            (
+                ((width as f64 * scale).round() as u32).clamp(1, bound_width),
+                ((height as f64 * scale).round() as u32).clamp(1, bound_height),
+            )
+        };
+        // The middle of the image, `crop_width`x`crop_height` large.
+        let centered = |crop_width: u32, crop_height: u32| {
+            let (crop_width, crop_height) = (crop_width.min(width), crop_height.min(height));
+            (
+                (width - crop_width) / 2,
+                (height - crop_height) / 2,
+                crop_width,
+                crop_height,
+            )
+        };
+
+        match self {
+            Fit::Contain if width <= bound_width && height <= bound_height => {
+                (whole, (width, height))
+            }
+            Fit::Contain => (whole, scaled(scale_x.min(scale_y))),
+            Fit::Cover => {
+                let scale = scale_x.max(scale_y);
+                let crop = centered(
+                    ((bound_width as f64 / scale).round() as u32).max(1),
+                    ((bound_height as f64 / scale).round() as u32).max(1),
+                );
+                (crop, (bound_width, bound_height))
+            }
+            Fit::Stretch => (whole, (bound_width, bound_height)),
+            Fit::Original => {
+                let crop = centered(bound_width, bound_height);
+                (crop, (crop.2, crop.3))
+            }
+            Fit::Integer => {
+                let factor = scale_x.min(scale_y).floor();
+                if factor >= 1.0 {
+                    (whole, scaled(factor))
+                } else {
+                    Fit::Contain.layout(size, bounds)
+                }
+            }
+        }
+    }
+}
+
+impl Filter {
+    pub fn next(self) -> Self {
+        match self {
+            Filter::Nearest => Filter::Triangle,
+            Filter::Triangle => Filter::CatmullRom,
+            Filter::CatmullRom => Filter::Gaussian,
+            Filter::Gaussian => Filter::Lanczos3,
+            Filter::Lanczos3 => Filter::Nearest,
+        }
+    }
+
+    pub fn filter_type(self) -> FilterType {
+        match self {
+            Filter::Nearest => FilterType::Nearest,
+            Filter::Triangle => FilterType::Triangle,
+            Filter::CatmullRom => FilterType::CatmullRom,
+            Filter::Gaussian => FilterType::Gaussian,
+            Filter::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
+impl Scaling {
+    /// Resizes `img` for `cols`x`rows` cells, each about twice as tall as wide
+    /// and drawn with `samples` pixels. Returns the image and the columns and
+    /// rows it covers.
+    pub fn fit_cells(
+        &self,
+        img: &DynamicImage,
+        cols: u32,
+        rows: u32,
+        samples: (u32, u32),
+    ) -> (DynamicImage, u32, u32) {
+        // The last row is left empty.
+        let rows = rows.saturating_sub(1).max(1);
+        let (crop, (width, height)) = self.fit.layout(img.dimensions(), (cols, 2 * rows));
+        let (cols, rows) = (width, height.div_ceil(2));
+
+        let img = self.resize(img, crop, (cols * samples.0, rows * samples.1));
+        (img, cols, rows)
+    }
+
+    /// Resizes `img` for `cols`x`rows` cells of `cell` pixels. Returns the image
+    /// and the columns and rows it covers.
+    pub fn fit_pixels(
+        &self,
+        img: &DynamicImage,
+        cols: u32,
+        rows: u32,
+        cell: (u32, u32),
+    ) -> (DynamicImage, u32, u32) {
+        let (crop, size) = self
+            .fit
+            .layout(img.dimensions(), (cols * cell.0, rows * cell.1));
+
+        let img = self.resize(img, crop, size);
+        let cols = size.0.div_ceil(cell.0).max(1);
+        let rows = size.1.div_ceil(cell.1).max(1);
+        (img, cols, rows)
+    }
+
+    fn resize(
+        &self,
+        img: &DynamicImage,
+        (x, y, width, height): (u32, u32, u32, u32),
+        size: (u32, u32),
+    ) -> DynamicImage {
+        let img = if (width, height) == img.dimensions() {
+            img.clone()
+        } else {
+            img.crop_imm(x, y, width, height)
+        };
+
+        if (width, height) == size {
+            img
+        } else {
+            img.resize_exact(size.0, size.1, self.filter.filter_type())
+        }
+    }
+}
+
+impl Display for Scaling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {}", self.fit, self.filter)
+    }
+}
+
+impl Display for Fit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            Fit::Contain => "contain",
+            Fit::Cover => "cover",
+            Fit::Stretch => "stretch",
+            Fit::Original => "original",
+            Fit::Integer => "integer",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            Filter::Nearest => "nearest",
+            Filter::Triangle => "triangle",
+            Filter::CatmullRom => "catmull-rom",
+            Filter::Gaussian => "gaussian",
+            Filter::Lanczos3 => "lanczos3",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl FromStr for Fit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "contain" => Ok(Fit::Contain),
+            "cover" | "fill" => Ok(Fit::Cover),
+            "stretch" => Ok(Fit::Stretch),
+            "original" | "none" => Ok(Fit::Original),
+            "integer" => Ok(Fit::Integer),
+            _ => Err(format!("Unknown fit mode: {}", s)),
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nearest" => Ok(Filter::Nearest),
+            "triangle" | "bilinear" => Ok(Filter::Triangle),
+            "catmull-rom" | "catmullrom" | "bicubic" => Ok(Filter::CatmullRom),
+            "gaussian" => Ok(Filter::Gaussian),
+            "lanczos3" | "lanczos" => Ok(Filter::Lanczos3),
+            _ => Err(format!("Unknown resampling filter: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: (u32, u32) = (100, 50);
+
+    #[test]
+    fn contains_large_images_only() {
+        assert_eq!(
+            Fit::Contain.layout((40, 20), BOUNDS),
+            ((0, 0, 40, 20), (40, 20))
+        );
+        assert_eq!(
+            Fit::Contain.layout((400, 100), BOUNDS),
+            ((0, 0, 400, 100), (100, 25))
+        );
+        assert_eq!(
+            Fit::Stretch.layout((40, 20), BOUNDS),
+            ((0, 0, 40, 20), (100, 50))
+        );
+    }
+
+    #[test]
+    fn covers_the_bounds_cropping_the_middle() {
+        assert_eq!(
+            Fit::Cover.layout((20, 20), BOUNDS),
+            ((0, 5, 20, 10), (100, 50))
+        );
+        assert_eq!(
+            Fit::Cover.layout((400, 400), BOUNDS),
+            ((0, 100, 400, 200), (100, 50))
+        );
+    }
+
+    #[test]
+    fn keeps_the_original_size_cropping_the_middle() {
+        assert_eq!(
+            Fit::Original.layout((40, 20), BOUNDS),
+            ((0, 0, 40, 20), (40, 20))
+        );
+        assert_eq!(
+            Fit::Original.layout((400, 300), BOUNDS),
+            ((150, 125, 100, 50), (100, 50))
+        );
+    }
+
+    #[test]
+    fn enlarges_by_whole_factors() {
+        assert_eq!(
+            Fit::Integer.layout((30, 20), BOUNDS),
+            ((0, 0, 30, 20), (60, 40))
+        );
+        assert_eq!(
+            Fit::Integer.layout((50, 25), BOUNDS),
+            ((0, 0, 50, 25), (100, 50))
+        );
+        // Large images shrink like with contain.
+        assert_eq!(
+            Fit::Integer.layout((400, 100), BOUNDS),
+            ((0, 0, 400, 100), (100, 25))
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use crate::graphics;
+use image::{Rgba, RgbaImage};
+use rgb::RGB8;
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// Asks the terminal for its background color.
+const BACKGROUND_QUERY: &str = "\x1b]11;?\x1b\\";
+
+/// Colors of the checkerboard squares.
+const CHECKERBOARD: [RGB8; 2] = [RGB8::new(0x99, 0x99, 0x99), RGB8::new(0x66, 0x66, 0x66)];
+
+/// How transparent pixels are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transparency {
+    pub backdrop: Backdrop,
+    /// Color of the solid backdrop.
+    pub color: RGB8,
+    /// Background color of the terminal, when it answered.
+    pub background: Option<RGB8>,
+}
+
+/// What transparent pixels are composited over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backdrop {
+    /// The background color of the terminal.
+    #[default]
+    Terminal,
+    Checkerboard,
+    /// A solid color.
+    Color,
+}
+
+impl Default for Transparency {
+    fn default() -> Self {
+        Self {
+            backdrop: Backdrop::default(),
+            color: RGB8::new(0xff, 0xff, 0xff),
+            background: None,
+        }
+    }
+}
+
+impl Backdrop {
+    pub fn next(self) -> Self {
+        match self {
+            Backdrop::Terminal => Backdrop::Checkerboard,
+            Backdrop::Checkerboard => Backdrop::Color,
+            Backdrop::Color => Backdrop::Terminal,
+        }
+    }
+}
+
+impl Transparency {
+    /// Composites `img` over the backdrop, with checkerboard squares of
+    /// `square` pixels.
+    ///
+    /// Without the terminal background, pixels are either left to the
+    /// terminal or drawn opaque.
+    pub fn composite(&self, img: &mut RgbaImage, square: (u32, u32)) {
+        let square = (square.0.max(1), square.1.max(1));
+        let backdrop = |x: u32, y: u32| match self.backdrop {
+            Backdrop::Terminal => self.background,
+            Backdrop::Checkerboard => {
+                Some(CHECKERBOARD[((x / square.0 + y / square.1) % 2) as usize])
+            }
+            Backdrop::Color => Some(self.color),
+        };
+
+        for (x, y, pixel) in img.enumerate_pixels_mut() {
+            let Rgba([r, g, b, a]) = *pixel;
+            if a == 255 {
+                continue;
+            }
+
+            *pixel = match backdrop(x, y) {
+                Some(bg) => {
+                    let blend = |fg: u8, bg: u8| {
+                        ((fg as u32 * a as u32 + bg as u32 * (255 - a as u32) + 127) / 255) as u8
+                    };
+                    Rgba([blend(r, bg.r), blend(g, bg.g), blend(b, bg.b), 255])
+                }
+                None if a < 128 => Rgba([r, g, b, 0]),
+                None => Rgba([r, g, b, 255]),
+            };
+        }
+    }
+
+    /// Queries the background color of the terminal, which is only answered
+    /// before the TUI starts reading the input.
+    pub fn detect_background(&mut self) {
+        self.background = graphics::query(BACKGROUND_QUERY).and_then(|r| parse_background(&r));
+    }
+}
+
+/// Parses an answer like `ESC ] 11 ; rgb:ffff/ffff/ffff ESC \`.
+fn parse_background(response: &str) -> Option<RGB8> {
+    let start = response.find("]11;rgb:")? + "]11;rgb:".len();
+    let end = response[start..]
+        .find(|c: char| c != '/' && !c.is_ascii_hexdigit())
+        .map_or(response.len(), |end| start + end);
+
+    let channels = response[start..end]
+        .split('/')
+        .map(|hex| {
+            let max = 16u64.checked_pow(hex.len() as u32)? - 1;
+            let value = u64::from_str_radix(hex, 16).ok()?;
+            Some((value.checked_mul(255)? / max.max(1)) as u8)
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    match channels[..] {
+        [r, g, b] => Some(RGB8::new(r, g, b)),
+        _ => None,
+    }
+}
+
+/// Parses a `#rrggbb` or `rrggbb` color.
+pub fn parse_color(s: &str) -> Result<RGB8, String> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+    };
+
+    match (hex.len(), channel(0), channel(2), channel(4)) {
+        (6, Some(r), Some(g), Some(b)) => Ok(RGB8::new(r, g, b)),
+        _ => Err(format!("Invalid color, expected #rrggbb: {}", s)),
+    }
+}
+
+impl Display for Transparency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = |c: RGB8| format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b);
+        match (self.backdrop, self.background) {
+            (Backdrop::Terminal, Some(background)) => write!(f, "terminal ({})", hex(background)),
+            (Backdrop::Terminal, None) => write!(f, "terminal (unknown)"),
+            (Backdrop::Checkerboard, _) => write!(f, "checkerboard"),
+            (Backdrop::Color, _) => write!(f, "{}", hex(self.color)),
+        }
+    }
+}
+
+impl FromStr for Backdrop {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "terminal" => Ok(Backdrop::Terminal),
+            "checkerboard" | "checker" => Ok(Backdrop::Checkerboard),
+            "color" | "solid" => Ok(Backdrop::Color),
+            _ => Err(format!("Unknown backdrop: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_background_answers() {
+        let orange = Some(RGB8::new(255, 128, 0));
+        assert_eq!(parse_background("\x1b]11;rgb:ffff/8080/0000\x1b\\"), orange);
+        assert_eq!(parse_background("\x1b]11;rgb:ff/80/00\x07"), orange);
+        assert_eq!(
+            parse_background("\x1b]11;rgb:fffffff/0000000/8000000\x1b\\"),
+            Some(RGB8::new(255, 0, 127))
+        );
+    }
+
+    #[test]
+    fn rejects_garbage_answers() {
+        assert_eq!(parse_background(""), None);
+        assert_eq!(parse_background("\x1b[?62;4c"), None);
+        assert_eq!(parse_background("\x1b]11;rgb:zz/00/00\x1b\\"), None);
+        assert_eq!(parse_background("\x1b]11;rgb:ff/ff\x1b\\"), None);
+        assert_eq!(parse_background("\x1b]11;rgb:ff//ff\x1b\\"), None);
+        assert_eq!(
+            parse_background("\x1b]11;rgb:ffffffffffffffff/0/0\x1b\\"),
+            None
+        );
+    }
+
+    /// Composites a row of four half transparent orange pixels and an opaque one.
+    fn composite(transparency: Transparency) -> Vec<[u8; 4]> {
+        let mut img = RgbaImage::from_pixel(5, 1, Rgba([200, 100, 0, 128]));
+        img.put_pixel(4, 0, Rgba([1, 2, 3, 255]));
+        transparency.composite(&mut img, (2, 2));
+        img.pixels().map(|pixel| pixel.0).collect()
+    }
+
+    #[test]
+    fn composites_half_transparent_pixels() {
+        let over = |backdrop, background| {
+            composite(Transparency {
+                backdrop,
+                background,
+                ..Transparency::default()
+            })
+        };
+
+        let black = Some(RGB8::new(0, 0, 0));
+        assert_eq!(over(Backdrop::Terminal, black)[0], [100, 50, 0, 255]);
+        assert_eq!(over(Backdrop::Terminal, None)[0], [200, 100, 0, 255]);
+        assert_eq!(over(Backdrop::Color, None)[0], [227, 177, 127, 255]);
+
+        let checkerboard = over(Backdrop::Checkerboard, black);
+        assert_eq!(checkerboard[0], [177, 126, 76, 255]);
+        assert_eq!(checkerboard[1], [177, 126, 76, 255]);
+        assert_eq!(checkerboard[2], [151, 101, 51, 255]);
+        assert_eq!(checkerboard[4], [1, 2, 3, 255]);
+    }
+
+    #[test]
+    fn leaves_transparent_pixels_to_the_terminal() {
+        let mut img = RgbaImage::from_pixel(1, 1, Rgba([200, 100, 0, 127]));
+        Transparency::default().composite(&mut img, (1, 1));
+        assert_eq!(img.get_pixel(0, 0).0, [200, 100, 0, 0]);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::decoder::HdrImage;
+use image::{DynamicImage, Rgba, RgbaImage};
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// Stops added or removed by the exposure keys.
+pub const EXPOSURE_STEP: f32 = 0.5;
+
+/// Largest value of a half float, brighter values are clipped to it.
+const MAX_VALUE: f32 = 65504.0;
+
+/// How the linear values of high dynamic range images are mapped to
+/// displayable colors.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ToneMap {
+    pub operator: Operator,
+    /// Exposure compensation in stops.
+    pub exposure: f32,
+}
+
+/// Curve compressing linear values to `0.0..=1.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Operator {
+    /// Clips everything brighter than white.
+    Clamp,
+    Reinhard,
+    /// An approximation of the ACES filmic curve.
+    #[default]
+    Filmic,
+}
+
+impl Operator {
+    pub fn next(self) -> Self {
+        match self {
+            Operator::Clamp => Operator::Reinhard,
+            Operator::Reinhard => Operator::Filmic,
+            Operator::Filmic => Operator::Clamp,
+        }
+    }
+
+    fn apply(self, x: f32) -> f32 {
+        match self {
+            Operator::Clamp => x,
+            Operator::Reinhard => x / (1.0 + x),
+            // Krzysztof Narkowicz's fit, scaled so that mid gray stays close.
+            Operator::Filmic => {
+                let x = x * 0.6;
+                (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+            }
+        }
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            Operator::Clamp => "clamp",
+            Operator::Reinhard => "reinhard",
+            Operator::Filmic => "filmic",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl FromStr for Operator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clamp" => Ok(Operator::Clamp),
+            "reinhard" => Ok(Operator::Reinhard),
+            "filmic" | "aces" => Ok(Operator::Filmic),
+            _ => Err(format!("Unknown tone mapping operator: {}", s)),
+        }
+    }
+}
+
+impl ToneMap {
+    /// Exposes, compresses and encodes `img` to sRGB.
+    pub fn apply(&self, img: &HdrImage) -> DynamicImage {
+        let scale = self.exposure.exp2();
+        let channel = |v: f32| {
+            let v = self.operator.apply((v * scale).clamp(0.0, MAX_VALUE));
+            (srgb(v.clamp(0.0, 1.0)) * 255.0).round() as u8
+        };
+
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(img.width(), img.height(), |x, y| {
+            // NaNs are black and transparent.
+            let [r, g, b, a] = img
+                .get_pixel(x, y)
+                .0
+                .map(|v| if v.is_nan() { 0.0 } else { v });
+            Rgba([
+                channel(r),
+                channel(g),
+                channel(b),
+                (a.clamp(0.0, 1.0) * 255.0).round() as u8,
+            ])
+        }))
+    }
+}
+
+impl Display for ToneMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {:+.1} EV", self.operator, self.exposure)
+    }
+}
+
+/// Encodes a linear value with the sRGB transfer function.
+pub(crate) fn srgb(v: f32) -> f32 {
+    if v <= 0.003_130_8 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPERATORS: [Operator; 3] = [Operator::Clamp, Operator::Reinhard, Operator::Filmic];
+
+    fn tone_map(operator: Operator, exposure: f32, values: &[[f32; 4]]) -> Vec<[u8; 4]> {
+        let img = HdrImage::from_fn(values.len() as u32, 1, |x, _| Rgba(values[x as usize]));
+        ToneMap { operator, exposure }
+            .apply(&img)
+            .to_rgba8()
+            .pixels()
+            .map(|pixel| pixel.0)
+            .collect()
+    }
+
+    #[test]
+    fn compresses_with_each_operator() {
+        let values = [[0.0, 0.5, 1.0, 1.0]];
+        assert_eq!(
+            tone_map(Operator::Clamp, 0.0, &values),
+            [[0, 188, 255, 255]]
+        );
+        assert_eq!(
+            tone_map(Operator::Reinhard, 0.0, &values),
+            [[0, 156, 188, 255]]
+        );
+        assert_eq!(
+            tone_map(Operator::Filmic, 0.0, &values),
+            [[0, 177, 214, 255]]
+        );
+
+        let bright = [[4.0, 100.0, f32::INFINITY, 1.0]];
+        assert_eq!(
+            tone_map(Operator::Clamp, 0.0, &bright),
+            [[255, 255, 255, 255]]
+        );
+    }
+
+    #[test]
+    fn doubles_the_light_per_stop() {
+        let values = [[0.05, 0.125, 0.25, 1.0]];
+        let doubled = [[0.1, 0.25, 0.5, 1.0]];
+        let halved = [[0.025, 0.0625, 0.125, 1.0]];
+        for operator in OPERATORS {
+            assert_eq!(
+                tone_map(operator, 1.0, &values),
+                tone_map(operator, 0.0, &doubled)
+            );
+            assert_eq!(
+                tone_map(operator, -1.0, &values),
+                tone_map(operator, 0.0, &halved)
+            );
+        }
+    }
+
+    #[test]
+    fn turns_nans_black_and_transparent() {
+        for operator in OPERATORS {
+            let values = [
+                [f32::NAN, f32::NAN, f32::NAN, f32::NAN],
+                [f32::NAN, -1.0, 0.0, 0.5],
+            ];
+            assert_eq!(
+                tone_map(operator, 0.0, &values),
+                [[0, 0, 0, 0], [0, 0, 0, 128]]
+            );
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use image::DynamicImage;
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// A rotation by quarter turns, possibly mirrored: the eight orientations of
+/// the EXIF Orientation tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Transform {
+    /// Whether the image is flipped horizontally, before it is rotated.
+    pub flip: bool,
+    /// Clockwise quarter turns, `0..4`.
+    pub turns: u8,
+}
+
+/// Axis of a flip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flip {
+    Horizontal,
+    Vertical,
+}
+
+impl Transform {
+    /// The transform displaying upright an image with the EXIF orientation
+    /// `orientation`, which is ignored when invalid.
+    pub fn from_exif(orientation: u32) -> Self {
+        let (flip, turns) = match orientation {
+            2 => (true, 0),
+            3 => (false, 2),
+            4 => (true, 2),
+            5 => (true, 3),
+            6 => (false, 1),
+            7 => (true, 1),
+            8 => (false, 3),
+            _ => (false, 0),
+        };
+        Self { flip, turns }
+    }
+
+    /// Rotation by `degrees` clockwise, a multiple of 90.
+    pub fn rotation(degrees: i64) -> Result<Self, String> {
+        if degrees % 90 != 0 {
+            return Err(format!(
+                "Images can only be rotated by multiples of 90 degrees: {}",
+                degrees
+            ));
+        }
+        Ok(Self {
+            flip: false,
+            turns: (degrees / 90).rem_euclid(4) as u8,
+        })
+    }
+
+    /// This transform followed by `other`.
+    pub fn then(self, other: Self) -> Self {
+        // Flipping reverses the direction of the turns before it.
+        let turns = if other.flip {
+            4 - self.turns
+        } else {
+            self.turns
+        };
+        Self {
+            flip: self.flip != other.flip,
+            turns: (turns + other.turns) % 4,
+        }
+    }
+
+    pub fn rotate_right(self) -> Self {
+        self.then(Self {
+            flip: false,
+            turns: 1,
+        })
+    }
+
+    pub fn rotate_left(self) -> Self {
+        self.then(Self {
+            flip: false,
+            turns: 3,
+        })
+    }
+
+    pub fn flip(self, axis: Flip) -> Self {
+        let turns = match axis {
+            Flip::Horizontal => 0,
+            Flip::Vertical => 2,
+        };
+        self.then(Self { flip: true, turns })
+    }
+
+    pub fn is_identity(self) -> bool {
+        self == Self::default()
+    }
+
+    /// Dimensions of an image of `dimensions` once transformed.
+    pub fn dimensions(self, (width, height): (u32, u32)) -> (u32, u32) {
+        if self.turns % 2 == 1 {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    pub fn apply(self, img: DynamicImage) -> DynamicImage {
+        let img = if self.flip { img.fliph() } else { img };
+        match self.turns {
+            1 => img.rotate90(),
+            2 => img.rotate180(),
+            3 => img.rotate270(),
+            _ => img,
+        }
+    }
+}
+
+impl Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.flip, self.turns) {
+            (false, 0) => write!(f, "none"),
+            (false, turns) => write!(f, "rotated {}°", turns as u32 * 90),
+            (true, 0) => write!(f, "flipped horizontally"),
+            (true, 2) => write!(f, "flipped vertically"),
+            (true, turns) => write!(f, "flipped horizontally, rotated {}°", turns as u32 * 90),
+        }
+    }
+}
+
+impl FromStr for Flip {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "horizontal" | "h" => Ok(Flip::Horizontal),
+            "vertical" | "v" => Ok(Flip::Vertical),
+            _ => Err(format!("Unknown flip: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, RgbImage};
+
+    /// A 3x2 image whose pixels all differ.
+    fn image() -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(3, 2, |x, y| {
+            image::Rgb([x as u8, y as u8, (x + 3 * y) as u8])
+        }))
+    }
+
+    /// Displays upright an image with the EXIF orientation, as defined by the
+    /// specification.
+    fn upright(img: &DynamicImage, orientation: u32) -> DynamicImage {
+        match orientation {
+            2 => img.fliph(),
+            3 => img.rotate180(),
+            4 => img.flipv(),
+            5 => img.rotate90().fliph(),
+            6 => img.rotate90(),
+            7 => img.rotate270().fliph(),
+            8 => img.rotate270(),
+            _ => img.clone(),
+        }
+    }
+
+    /// A transform step and the operation of `image` it matches.
+    type Step = (
+        fn(Transform) -> Transform,
+        fn(&DynamicImage) -> DynamicImage,
+    );
+
+    fn assert_same(actual: &DynamicImage, expected: &DynamicImage) {
+        assert_eq!(actual.dimensions(), expected.dimensions());
+        assert_eq!(actual.to_bytes(), expected.to_bytes());
+    }
+
+    #[test]
+    fn shows_each_exif_orientation_upright() {
+        let img = image();
+        for orientation in 1..=8 {
+            let transform = Transform::from_exif(orientation);
+            let expected = upright(&img, orientation);
+            assert_same(&transform.apply(img.clone()), &expected);
+            assert_eq!(
+                transform.dimensions(img.dimensions()),
+                expected.dimensions()
+            );
+        }
+        assert!(Transform::from_exif(0).is_identity());
+        assert!(Transform::from_exif(9).is_identity());
+    }
+
+    #[test]
+    fn composes_rotations_and_flips_after_the_orientation() {
+        let img = image();
+        let steps: [Step; 4] = [
+            (Transform::rotate_left, DynamicImage::rotate270),
+            (Transform::rotate_right, DynamicImage::rotate90),
+            (|t| t.flip(Flip::Horizontal), DynamicImage::fliph),
+            (|t| t.flip(Flip::Vertical), DynamicImage::flipv),
+        ];
+
+        for orientation in 1..=8 {
+            let transform = Transform::from_exif(orientation);
+            let shown = upright(&img, orientation);
+            for (step, op) in steps {
+                assert_same(&step(transform).apply(img.clone()), &op(&shown));
+                for (second, second_op) in steps {
+                    let composed = second(step(transform));
+                    assert_same(&composed.apply(img.clone()), &second_op(&op(&shown)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn undoes_rotations_and_flips() {
+        for orientation in 1..=8 {
+            let transform = Transform::from_exif(orientation);
+            assert_eq!(transform.rotate_right().rotate_left(), transform);
+            assert_eq!(
+                transform
+                    .rotate_left()
+                    .rotate_left()
+                    .rotate_left()
+                    .rotate_left(),
+                transform
+            );
+            for axis in [Flip::Horizontal, Flip::Vertical] {
+                assert_eq!(transform.flip(axis).flip(axis), transform);
+            }
+        }
+        assert_eq!(
+            Transform::default()
+                .flip(Flip::Horizontal)
+                .flip(Flip::Vertical),
+            Transform::rotation(180).unwrap()
+        );
+    }
+}
@@ -1,36 +1,138 @@
+use crate::{
+    color_vision::Deficiency,
+    decoder::{self, Format},
+};
 use std::{
+    fmt::{self, Display},
     fs,
     path::{Path, PathBuf},
 };
 
-static FILE_TYPES: [&str; 6] = ["png", "jpg", "jpeg", "webp", "bmp", "gif"];
-
 #[derive(Debug, Clone)]
 pub enum RunMode {
     CLI,
     TUI,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ImageMode {
     Rgba,
     GrayScale,
+    /// Shows the colors as seen with a color vision deficiency.
+    Simulate(Deficiency),
+}
+
+impl ImageMode {
+    pub fn next(self) -> Self {
+        match self {
+            ImageMode::Rgba => ImageMode::GrayScale,
+            ImageMode::GrayScale => ImageMode::Simulate(Deficiency::Protanopia),
+            ImageMode::Simulate(Deficiency::Protanopia) => {
+                ImageMode::Simulate(Deficiency::Deuteranopia)
+            }
+            ImageMode::Simulate(Deficiency::Deuteranopia) => {
+                ImageMode::Simulate(Deficiency::Tritanopia)
+            }
+            ImageMode::Simulate(Deficiency::Tritanopia) => {
+                ImageMode::Simulate(Deficiency::Achromatopsia)
+            }
+            ImageMode::Simulate(Deficiency::Achromatopsia) => ImageMode::Rgba,
+        }
+    }
+}
+
+impl Display for ImageMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageMode::Rgba => write!(f, "color"),
+            ImageMode::GrayScale => write!(f, "gray scale"),
+            ImageMode::Simulate(deficiency) => write!(f, "{}", deficiency),
+        }
+    }
+}
+
+/// A file of the image list.
+#[derive(Debug, Clone)]
+pub struct ImageFile {
+    pub path: PathBuf,
+    /// `None` when the file looks like an image but cannot be decoded.
+    pub format: Option<Format>,
+}
+
+impl ImageFile {
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    pub fn is_decodable(&self) -> bool {
+        self.format.is_some()
+    }
+}
+
+/// Extensions set with `--include` and `--exclude`, in lowercase.
+#[derive(Debug, Clone, Default)]
+pub struct FileFilter {
+    /// Only files with these extensions are listed, unless empty.
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl FileFilter {
+    /// Parses a comma separated list of extensions such as `png,.JPG`.
+    pub fn parse_extensions(s: &str) -> Vec<String> {
+        s.split(',')
+            .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+            .filter(|ext| !ext.is_empty())
+            .collect()
+    }
+
+    fn accepts(&self, extensions: &[String]) -> bool {
+        let included =
+            self.include.is_empty() || extensions.iter().any(|e| self.include.contains(e));
+        let excluded = extensions.iter().any(|e| self.exclude.contains(e));
+        included && !excluded
+    }
 }
 
-pub fn get_image_paths(path: &str) -> Vec<PathBuf> {
+/// Lists the images in the directory at `path`. Files are recognized from
+/// their header, and files named like images that cannot be decoded are
+/// listed too so that they can be marked.
+pub fn get_image_paths(path: &str, filter: &FileFilter) -> Vec<ImageFile> {
     let paths = fs::read_dir(path).unwrap();
     let mut result = vec![];
 
     for path in paths {
         let path = path.unwrap().path();
-        if let Some(ext) = path.extension() {
-            if FILE_TYPES.iter().any(|&f| f == ext) {
-                result.push(path);
-            }
+        if !path.is_file() {
+            continue;
+        }
+
+        let format = decoder::detect_format(&path);
+        let ext = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        let looks_like_image = ext.as_ref().is_some_and(|ext| {
+            Format::from_extension(ext).is_some() || filter.include.contains(ext)
+        });
+        if format.is_none() && !looks_like_image {
+            continue;
+        }
+
+        // Misnamed and extensionless files also match the extensions of their format.
+        let mut extensions: Vec<String> = ext.into_iter().collect();
+        if let Some(format) = format {
+            extensions.extend(format.extensions().iter().map(|ext| ext.to_string()));
+        }
+        if filter.accepts(&extensions) {
+            result.push(ImageFile { path, format });
         }
     }
 
-    result.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+    result.sort_by(|a, b| a.path.file_name().cmp(&b.path.file_name()));
 
     result
 }
@@ -39,11 +141,10 @@ pub fn select_mode(args: &[String]) -> RunMode {
     match args.len() {
         0 => RunMode::TUI,
         1 => {
-            if Path::new(&args[0]).is_dir() {
+            let path = Path::new(&args[0]);
+            if path.is_dir() {
                 RunMode::TUI
-            } else if FILE_TYPES
-                .contains(&Path::new(&args[0]).extension().unwrap().to_str().unwrap())
-            {
+            } else if path.is_file() && decoder::detect_format(path).is_some() {
                 RunMode::CLI
             } else {
                 eprintln!("The argument must be a directory or a single image file.");
@@ -56,3 +157,53 @@ pub fn select_mode(args: &[String]) -> RunMode {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include: &str, exclude: &str) -> FileFilter {
+        FileFilter {
+            include: FileFilter::parse_extensions(include),
+            exclude: FileFilter::parse_extensions(exclude),
+        }
+    }
+
+    fn extensions(extensions: &[&str]) -> Vec<String> {
+        extensions.iter().map(|ext| ext.to_string()).collect()
+    }
+
+    #[test]
+    fn normalizes_extension_lists() {
+        assert_eq!(
+            FileFilter::parse_extensions(" png, .JPG,,jpeg "),
+            ["png", "jpg", "jpeg"]
+        );
+        assert!(FileFilter::parse_extensions("").is_empty());
+    }
+
+    #[test]
+    fn accepts_included_and_not_excluded_extensions() {
+        let all = filter("", "");
+        assert!(all.accepts(&extensions(&["png"])));
+        assert!(all.accepts(&[]));
+
+        let png = filter("png", "");
+        assert!(png.accepts(&extensions(&["png", "apng"])));
+        assert!(!png.accepts(&extensions(&["jpg", "jpeg"])));
+        assert!(!png.accepts(&[]));
+
+        let no_gif = filter("", "gif");
+        assert!(no_gif.accepts(&extensions(&["png"])));
+        assert!(!no_gif.accepts(&extensions(&["gif"])));
+        assert!(!filter("gif", "gif").accepts(&extensions(&["gif"])));
+    }
+
+    #[test]
+    fn matches_misnamed_files_by_their_format() {
+        // A PNG named .jpg, listed with the extensions of both.
+        let misnamed = extensions(&["jpg", "png", "apng"]);
+        assert!(filter("png", "").accepts(&misnamed));
+        assert!(!filter("", "png").accepts(&misnamed));
+    }
+}
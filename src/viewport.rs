@@ -0,0 +1,319 @@
+use crate::renderer::scaling::Filter;
+use image::{DynamicImage, GenericImageView};
+use std::fmt::{self, Display};
+
+/// Factor applied by the zoom keys.
+const ZOOM_STEP: f32 = 1.5;
+
+/// Panel pixels per image pixel at the highest zoom.
+const MAX_SCALE: f32 = 64.0;
+
+/// Fraction of the visible area moved by the pan keys.
+const PAN_STEP: f32 = 0.25;
+
+/// Largest side vector images are rasterized at when zooming in.
+const MAX_RASTER_SIZE: u32 = 4096;
+
+/// How large the image is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Zoom {
+    /// The whole image fits in the panel.
+    #[default]
+    Fit,
+    FitWidth,
+    FitHeight,
+    /// Panel pixels per image pixel, `1.0` shows the pixels as they are.
+    Scale(f32),
+}
+
+/// The part of the image shown in the panel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub zoom: Zoom,
+    /// Center of the visible area, as a fraction of the image size.
+    pub center: (f32, f32),
+}
+
+/// A visible area, in pixels of the image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Area {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            zoom: Zoom::Fit,
+            center: (0.5, 0.5),
+        }
+    }
+}
+
+impl Viewport {
+    /// Panel pixels per image pixel, for an image of `dimensions` shown in a
+    /// panel of `resolution` pixels.
+    pub fn scale(&self, dimensions: (u32, u32), resolution: (u32, u32)) -> f32 {
+        let fit_width = resolution.0 as f32 / dimensions.0.max(1) as f32;
+        let fit_height = resolution.1 as f32 / dimensions.1.max(1) as f32;
+        match self.zoom {
+            // Small images are not enlarged to fit.
+            Zoom::Fit => fit_width.min(fit_height).min(1.0),
+            Zoom::FitWidth => fit_width,
+            Zoom::FitHeight => fit_height,
+            Zoom::Scale(scale) => scale,
+        }
+    }
+
+    pub fn zoom_in(&mut self, dimensions: (u32, u32), resolution: (u32, u32)) {
+        let scale = self.scale(dimensions, resolution) * ZOOM_STEP;
+        self.zoom = Zoom::Scale(scale.min(MAX_SCALE));
+    }
+
+    /// Zooms out, down to the size fitting the panel.
+    pub fn zoom_out(&mut self, dimensions: (u32, u32), resolution: (u32, u32)) {
+        let scale = self.scale(dimensions, resolution) / ZOOM_STEP;
+        let fit = Viewport::default().scale(dimensions, resolution);
+        self.zoom = if scale <= fit {
+            Zoom::Fit
+        } else {
+            Zoom::Scale(scale)
+        };
+    }
+
+    /// Moves the visible area by a step in the direction of `dx` and `dy`,
+    /// staying inside the image.
+    pub fn pan(&mut self, dx: i32, dy: i32, dimensions: (u32, u32), resolution: (u32, u32)) {
+        let (x, y, width, height) = self.visible(dimensions, resolution);
+        self.center = (
+            x + width / 2.0 + dx as f32 * width * PAN_STEP,
+            y + height / 2.0 + dy as f32 * height * PAN_STEP,
+        );
+        let (x, y, width, height) = self.visible(dimensions, resolution);
+        self.center = (x + width / 2.0, y + height / 2.0);
+    }
+
+    /// The visible area as fractions `(x, y, width, height)` of the image size.
+    fn visible(&self, dimensions: (u32, u32), resolution: (u32, u32)) -> (f32, f32, f32, f32) {
+        let scale = self.scale(dimensions, resolution);
+        let width = (resolution.0 as f32 / scale / dimensions.0.max(1) as f32).min(1.0);
+        let height = (resolution.1 as f32 / scale / dimensions.1.max(1) as f32).min(1.0);
+        let x = self.center.0.clamp(width / 2.0, 1.0 - width / 2.0) - width / 2.0;
+        let y = self.center.1.clamp(height / 2.0, 1.0 - height / 2.0) - height / 2.0;
+        (x, y, width, height)
+    }
+
+    /// The visible area of an image of `dimensions`, or of an image rasterized
+    /// at another size when `size` differs.
+    pub fn area(&self, dimensions: (u32, u32), resolution: (u32, u32), size: (u32, u32)) -> Area {
+        let (x, y, width, height) = self.visible(dimensions, resolution);
+        let x = (x * size.0 as f32).round() as u32;
+        let y = (y * size.1 as f32).round() as u32;
+        Area {
+            x,
+            y,
+            width: ((width * size.0 as f32).round() as u32).clamp(1, size.0 - x.min(size.0 - 1)),
+            height: ((height * size.1 as f32).round() as u32).clamp(1, size.1 - y.min(size.1 - 1)),
+        }
+    }
+
+    /// Crops the visible area of `img`, an image of `dimensions` that may have
+    /// been rasterized at another size, and enlarges it with `filter` to the
+    /// size it covers in the panel since renderers only shrink images to fit.
+    pub fn crop(
+        &self,
+        img: DynamicImage,
+        dimensions: (u32, u32),
+        resolution: (u32, u32),
+        filter: Filter,
+    ) -> DynamicImage {
+        if self.zoom == Zoom::Fit {
+            return img;
+        }
+
+        let size = img.dimensions();
+        let area = self.area(dimensions, resolution, size);
+        let img = if (area.width, area.height) == size {
+            img
+        } else {
+            img.crop_imm(area.x, area.y, area.width, area.height)
+        };
+
+        let scale = self.scale(dimensions, resolution);
+        let width =
+            (area.width as f32 * dimensions.0 as f32 / size.0 as f32 * scale).round() as u32;
+        let height =
+            (area.height as f32 * dimensions.1 as f32 / size.1 as f32 * scale).round() as u32;
+        let (width, height) = (width.min(resolution.0), height.min(resolution.1));
+        if width <= area.width && height <= area.height {
+            return img;
+        }
+
+        img.resize_exact(width.max(1), height.max(1), filter.filter_type())
+    }
+
+    /// Size to rasterize a vector image of `dimensions` at to show it sharp.
+    pub fn raster_size(&self, dimensions: (u32, u32), resolution: (u32, u32)) -> (u32, u32) {
+        let largest = dimensions.0.max(dimensions.1).max(1) as f32;
+        let scale = self
+            .scale(dimensions, resolution)
+            .min(MAX_RASTER_SIZE as f32 / largest);
+        let size = |v: u32| ((v as f32 * scale).round() as u32).max(1);
+        (size(dimensions.0), size(dimensions.1))
+    }
+}
+
+impl Display for Zoom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Zoom::Fit => write!(f, "fit"),
+            Zoom::FitWidth => write!(f, "fit width"),
+            Zoom::FitHeight => write!(f, "fit height"),
+            Zoom::Scale(scale) => write!(f, "{:.0}%", scale * 100.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IMAGE: (u32, u32) = (400, 300);
+    const PANEL: (u32, u32) = (100, 100);
+
+    fn zoomed(scale: f32) -> Viewport {
+        Viewport {
+            zoom: Zoom::Scale(scale),
+            ..Viewport::default()
+        }
+    }
+
+    #[test]
+    fn scales_to_the_zoom() {
+        let scale = |zoom, dimensions| {
+            Viewport {
+                zoom,
+                ..Viewport::default()
+            }
+            .scale(dimensions, PANEL)
+        };
+        assert_eq!(scale(Zoom::Fit, IMAGE), 0.25);
+        assert_eq!(scale(Zoom::FitWidth, IMAGE), 0.25);
+        assert_eq!(scale(Zoom::FitHeight, IMAGE), 1.0 / 3.0);
+        assert_eq!(scale(Zoom::Scale(2.0), IMAGE), 2.0);
+        // Small images fit without being enlarged, unless asked to.
+        assert_eq!(scale(Zoom::Fit, (50, 20)), 1.0);
+        assert_eq!(scale(Zoom::FitWidth, (50, 20)), 2.0);
+    }
+
+    #[test]
+    fn zooms_out_back_to_fit() {
+        let mut viewport = Viewport::default();
+        viewport.zoom_in(IMAGE, PANEL);
+        assert_eq!(viewport.zoom, Zoom::Scale(0.375));
+        viewport.zoom_out(IMAGE, PANEL);
+        assert_eq!(viewport.zoom, Zoom::Fit);
+        viewport.zoom_out(IMAGE, PANEL);
+        assert_eq!(viewport.zoom, Zoom::Fit);
+
+        let mut viewport = zoomed(0.3);
+        viewport.zoom_out(IMAGE, PANEL);
+        assert_eq!(viewport.zoom, Zoom::Fit);
+
+        let mut viewport = zoomed(60.0);
+        viewport.zoom_in(IMAGE, PANEL);
+        assert_eq!(viewport.zoom, Zoom::Scale(MAX_SCALE));
+    }
+
+    #[test]
+    fn pans_inside_the_image() {
+        let mut viewport = zoomed(1.0);
+        let area = |viewport: &Viewport| viewport.area(IMAGE, PANEL, IMAGE);
+        assert_eq!(
+            area(&viewport),
+            Area {
+                x: 150,
+                y: 100,
+                width: 100,
+                height: 100
+            }
+        );
+
+        viewport.pan(-1, 0, IMAGE, PANEL);
+        assert_eq!(area(&viewport).x, 125);
+        for _ in 0..10 {
+            viewport.pan(-1, -1, IMAGE, PANEL);
+        }
+        assert_eq!((area(&viewport).x, area(&viewport).y), (0, 0));
+        assert_eq!(viewport.center, (0.125, 1.0 / 6.0));
+
+        for _ in 0..20 {
+            viewport.pan(1, 1, IMAGE, PANEL);
+        }
+        assert_eq!((area(&viewport).x, area(&viewport).y), (300, 200));
+
+        // The whole image is visible when it fits, there is nothing to pan.
+        let mut viewport = Viewport::default();
+        viewport.pan(1, 1, IMAGE, PANEL);
+        assert_eq!(viewport.center, (0.5, 0.5));
+        assert_eq!(
+            area(&viewport),
+            Area {
+                x: 0,
+                y: 0,
+                width: 400,
+                height: 300
+            }
+        );
+    }
+
+    #[test]
+    fn crops_rasters_of_another_size() {
+        let viewport = zoomed(1.0);
+        // A vector image rasterized at twice its size.
+        assert_eq!(
+            viewport.area(IMAGE, PANEL, (800, 600)),
+            Area {
+                x: 300,
+                y: 200,
+                width: 200,
+                height: 200
+            }
+        );
+        let raster = DynamicImage::new_rgb8(800, 600);
+        let img = viewport.crop(raster, IMAGE, PANEL, Filter::Nearest);
+        assert_eq!(img.dimensions(), (200, 200));
+
+        // Pixels are enlarged to the panel size they cover.
+        let img = zoomed(2.0).crop(
+            DynamicImage::new_rgb8(400, 300),
+            IMAGE,
+            PANEL,
+            Filter::Nearest,
+        );
+        assert_eq!(img.dimensions(), (100, 100));
+
+        let img = Viewport::default().crop(
+            DynamicImage::new_rgb8(400, 300),
+            IMAGE,
+            PANEL,
+            Filter::Nearest,
+        );
+        assert_eq!(img.dimensions(), IMAGE);
+    }
+
+    #[test]
+    fn rasterizes_at_the_shown_size() {
+        assert_eq!(
+            Viewport::default().raster_size((200, 100), PANEL),
+            (100, 50)
+        );
+        assert_eq!(zoomed(3.0).raster_size((200, 100), PANEL), (600, 300));
+        assert_eq!(
+            zoomed(MAX_SCALE).raster_size((200, 100), PANEL),
+            (4096, 2048)
+        );
+    }
+}